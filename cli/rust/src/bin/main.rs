@@ -3,15 +3,26 @@
 //! This binary provides the standalone Rust implementation of the agents CLI,
 //! which can be used independently or as the core engine for the TypeScript CLI.
 
-use agents_core::Result;
+use agents_core::modules::compile::{DictionaryTranslator, NoopTranslator, Translator};
+use agents_core::modules::config::ConfigManager;
+use agents_core::modules::orchestrator::{ComposeOptions, SyncOptions, UpdateOptions};
+use agents_core::modules::sync::SyncRegistry;
+use agents_core::{AgentConfig, Orchestrator, Result, TemplateCache, TemplateType};
 use clap::{Parser, Subcommand};
+use std::path::{Path, PathBuf};
 use std::process;
 
+/// Configuration file read from the working directory
+const CONFIG_FILE: &str = "agents.prompts.json";
+
 #[derive(Parser)]
 #[command(name = "agents-core")]
 #[command(about = "Agents Core - Rust engine for prompt management")]
 #[command(version)]
 struct Cli {
+  /// Serve stale cached templates when a remote source is unreachable
+  #[arg(long, global = true)]
+  offline: bool,
   #[command(subcommand)]
   command: Commands,
 }
@@ -44,6 +55,35 @@ enum Commands {
     /// Interactive mode
     #[arg(short, long)]
     interactive: bool,
+    /// Template to compose from
+    #[arg(short, long)]
+    template: Option<String>,
+    /// JSON or TOML file with variable values
+    #[arg(long)]
+    vars: Option<String>,
+    /// Override a variable value
+    #[arg(long = "set", value_name = "KEY=VALUE")]
+    set: Vec<String>,
+    /// Output file, relative to the directory of the prompt type
+    #[arg(short, long, conflicts_with = "stdout")]
+    output: Option<String>,
+    /// Print the rendered prompt instead of writing it
+    #[arg(long)]
+    stdout: bool,
+    /// Overwrite an existing output file
+    #[arg(short, long)]
+    force: bool,
+  },
+  /// Compile `.src.md` prompt sources into their sibling `.md` files
+  Compile {
+    /// Source files or directories, defaults to the prompt directories
+    paths: Vec<PathBuf>,
+    /// JSON or TOML dictionary of term translations
+    #[arg(long)]
+    dictionary: Option<PathBuf>,
+    /// Recompile sources that did not change
+    #[arg(short, long)]
+    force: bool,
   },
   /// Prune old prompts
   Prune {
@@ -54,22 +94,92 @@ enum Commands {
     #[arg(short, long)]
     dry_run: bool,
   },
-  /// Sync with remote repository
+  /// Write the prompts to the files of the enabled AI tools
   Sync {
-    /// Remote repository URL
+    /// Targets to sync, defaults to the targets enabled in the configuration
+    #[arg(short, long = "target")]
+    targets: Vec<String>,
+    /// Show the files that would change without writing them
+    #[arg(short, long)]
+    dry_run: bool,
+    /// List every written file
     #[arg(short, long)]
-    remote: Option<String>,
-    /// Branch to sync with
+    verbose: bool,
+    /// List the available targets, marking the enabled ones
     #[arg(short, long)]
-    branch: Option<String>,
+    list: bool,
+  },
+  /// Inspect or clear the template cache
+  Cache {
+    #[command(subcommand)]
+    action: CacheAction,
+  },
+  /// Check and test template files
+  Template {
+    #[command(subcommand)]
+    action: TemplateAction,
   },
 }
 
+#[derive(Subcommand)]
+enum TemplateAction {
+  /// Report problems in template files
+  Lint {
+    /// Template directories, defaults to the local templates directory
+    paths: Vec<PathBuf>,
+    /// Output format
+    #[arg(long, value_enum, default_value_t = OutputFormat::Human)]
+    format: OutputFormat,
+  },
+  /// Render template fixtures and compare them with their snapshots
+  Test {
+    /// Template directories, defaults to the local templates directory
+    paths: Vec<PathBuf>,
+    /// Write the current output as the new snapshots
+    #[arg(short, long)]
+    update: bool,
+  },
+}
+
+#[derive(clap::ValueEnum, Clone, Copy, Debug)]
+enum OutputFormat {
+  Human,
+  Json,
+}
+
+#[derive(Subcommand)]
+enum CacheAction {
+  /// Show cache statistics
+  Stats,
+  /// Remove all cached entries
+  Clear,
+}
+
 #[derive(clap::ValueEnum, Clone, Debug)]
 enum PromptType {
+  UserMemory,
+  ProjectMemory,
+  /// Locale memory
+  #[value(alias = "locale-memory")]
   Memory,
   SubAgent,
   Cmd,
+  Skill,
+  Rule,
+}
+
+impl From<PromptType> for TemplateType {
+  fn from(value: PromptType) -> Self {
+    match value {
+      PromptType::UserMemory => TemplateType::UserMemory,
+      PromptType::ProjectMemory => TemplateType::ProjectMemory,
+      PromptType::Memory => TemplateType::LocaleMemory,
+      PromptType::SubAgent => TemplateType::SubAgent,
+      PromptType::Cmd => TemplateType::Command,
+      PromptType::Skill => TemplateType::Skill,
+      PromptType::Rule => TemplateType::Rule,
+    }
+  }
 }
 
 #[tokio::main]
@@ -78,14 +188,28 @@ async fn main() {
 
   let cli = Cli::parse();
 
-  if let Err(e) = run(cli.command).await {
+  if let Err(e) = run(cli).await {
     eprintln!("Error: {}", e);
     process::exit(1);
   }
 }
 
-async fn run(command: Commands) -> Result<()> {
-  match command {
+/// Load the configuration from the working directory, falling back to defaults
+/// when there is no configuration file
+fn load_config(offline: bool) -> Result<AgentConfig> {
+  let mut config = if Path::new(CONFIG_FILE).exists() {
+    ConfigManager::new(CONFIG_FILE).load()?
+  } else {
+    AgentConfig::default()
+  };
+  config.templates.cache.offline |= offline;
+  Ok(config)
+}
+
+async fn run(cli: Cli) -> Result<()> {
+  let config = load_config(cli.offline)?;
+
+  match cli.command {
     Commands::Init { force, template } => {
       println!("Initializing __ai/ repository...");
       println!("Force: {}, Template: {:?}", force, template);
@@ -93,18 +217,62 @@ async fn run(command: Commands) -> Result<()> {
       Ok(())
     }
     Commands::Update { backup, version } => {
-      println!("Updating repository...");
-      println!("Backup: {}, Version: {:?}", backup, version);
-      // TODO: Implement update logic
+      let result = Orchestrator::new(config)?
+        .update(UpdateOptions {
+          backup,
+          version,
+          silent: false,
+          verbose: false,
+        })
+        .await?;
+      println!("{}", result.message);
       Ok(())
     }
     Commands::Compose {
       r#type,
       interactive,
+      template,
+      vars,
+      set,
+      output,
+      stdout,
+      force,
+    } => {
+      let mut orchestrator = Orchestrator::new(config)?;
+      let composed = orchestrator
+        .compose(ComposeOptions {
+          r#type: r#type.into(),
+          interactive,
+          template,
+          vars_file: vars,
+          overrides: set,
+          output,
+          stdout,
+          force,
+          silent: stdout,
+          verbose: false,
+        })
+        .await?;
+      if stdout {
+        print!("{}", composed.content);
+      }
+      Ok(())
+    }
+    Commands::Compile {
+      paths,
+      dictionary,
+      force,
     } => {
-      println!("Composing {:?} prompt...", r#type);
-      println!("Interactive: {}", interactive);
-      // TODO: Implement compose logic
+      let translator: Box<dyn Translator> = match dictionary {
+        Some(path) => Box::new(DictionaryTranslator::load(path)?),
+        None => Box::new(NoopTranslator),
+      };
+      let report =
+        Orchestrator::new(config)?.compile_prompts(&paths, translator.as_ref(), force)?;
+      println!("{}", report);
+      if !report.is_success() {
+        process::exit(1);
+      }
       Ok(())
     }
     Commands::Prune { force, dry_run } => {
@@ -113,11 +281,74 @@ async fn run(command: Commands) -> Result<()> {
       // TODO: Implement prune logic
       Ok(())
     }
-    Commands::Sync { remote, branch } => {
-      println!("Syncing with remote...");
-      println!("Remote: {:?}, Branch: {:?}", remote, branch);
-      // TODO: Implement sync logic
+    Commands::Sync {
+      targets,
+      dry_run,
+      verbose,
+      list,
+    } => {
+      if list {
+        for target in SyncRegistry::new().targets() {
+          let enabled = config.sync.targets.iter().any(|id| id == target.id());
+          println!(
+            "{} {:<12} {}",
+            if enabled { "*" } else { " " },
+            target.id(),
+            target.description()
+          );
+        }
+        return Ok(());
+      }
+
+      let result = Orchestrator::new(config)?
+        .sync(SyncOptions {
+          root: PathBuf::from("."),
+          targets,
+          dry_run,
+          silent: false,
+          verbose: verbose || dry_run,
+        })
+        .await?;
+      println!("{}", result.message);
+      if !result.success {
+        process::exit(1);
+      }
+      Ok(())
+    }
+    Commands::Cache { action } => {
+      let mut cache = TemplateCache::open(&config.templates.cache)?;
+      match action {
+        CacheAction::Stats => println!("{}", cache.stats()?),
+        CacheAction::Clear => {
+          let cleared = cache.clear()?;
+          println!(
+            "Cleared {} cache entries ({} bytes)",
+            cleared.entries, cleared.total_size_bytes
+          );
+        }
+      }
       Ok(())
     }
+    Commands::Template { action } => match action {
+      TemplateAction::Lint { paths, format } => {
+        let report = Orchestrator::new(config)?.lint_templates(&paths)?;
+        match format {
+          OutputFormat::Human => println!("{}", report),
+          OutputFormat::Json => println!("{}", report.to_json()?),
+        }
+        if report.has_errors() {
+          process::exit(1);
+        }
+        Ok(())
+      }
+      TemplateAction::Test { paths, update } => {
+        let report = Orchestrator::new(config)?.test_templates(&paths, update)?;
+        println!("{}", report);
+        if !report.is_success() {
+          process::exit(1);
+        }
+        Ok(())
+      }
+    },
   }
 }
@@ -12,12 +12,14 @@ pub use crate::napi::*;
 
 // Re-export main types
 pub use modules::{
+  cache::{CacheStats, TemplateCache},
   config::{AgentConfig, PromptTemplate, TemplateType},
-  error::{AgentError, Result},
+  error::{AgentError, Result, VariableViolation},
   orchestrator::Orchestrator,
   platform::Platform,
+  remote::RemoteTemplates,
   storage::{BackupInfo, StorageManager},
-  template::TemplateRegistry,
+  template::{TemplateRegistry, TemplateSource},
 };
 
 /// Library version
@@ -0,0 +1,424 @@
+//! Template cache for the agents core system
+//!
+//! This module provides an on-disk cache for translated prompt bodies and remote
+//! template packs, honoring the configured size limit (LRU eviction), TTL expiry and source
+//! hash invalidation.
+
+use super::config::CacheSettings;
+use super::error::{AgentError, Result};
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+use std::collections::BTreeMap;
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::time::{SystemTime, UNIX_EPOCH};
+
+/// Name of the cache index file
+const INDEX_FILE: &str = "index.json";
+
+/// Cache entry metadata
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Eq)]
+pub struct CacheEntry {
+  /// Cache key
+  pub key: String,
+  /// Hash of the source the entry was produced from
+  pub source_hash: String,
+  /// Entry path relative to the cache directory
+  pub path: String,
+  /// Size of the entry in bytes
+  pub size_bytes: u64,
+  /// Time the entry was created or last revalidated
+  pub created_at: u64,
+  /// Time the entry was last read
+  pub accessed_at: u64,
+}
+
+/// Result of a cache lookup
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum CacheLookup {
+  /// Entry exists and is within its TTL
+  Fresh(CacheEntry),
+  /// Entry exists but its TTL has expired
+  Stale(CacheEntry),
+  /// No usable entry
+  Miss,
+}
+
+/// Cache statistics
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct CacheStats {
+  /// Whether caching is enabled
+  pub enabled: bool,
+  /// Cache directory
+  pub directory: String,
+  /// Number of entries
+  pub entries: usize,
+  /// Number of entries past their TTL
+  pub expired_entries: usize,
+  /// Total size of all entries in bytes
+  pub total_size_bytes: u64,
+  /// Maximum cache size in bytes
+  pub max_size_bytes: u64,
+}
+
+#[derive(Debug, Default, Serialize, Deserialize)]
+struct CacheIndex {
+  entries: BTreeMap<String, CacheEntry>,
+}
+
+/// On-disk template cache
+pub struct TemplateCache {
+  root: PathBuf,
+  settings: CacheSettings,
+  index: CacheIndex,
+}
+
+impl TemplateCache {
+  /// Open the cache described by the settings, loading its index if present
+  pub fn open(settings: &CacheSettings) -> Result<Self> {
+    let root = PathBuf::from(&settings.directory);
+    let index_path = root.join(INDEX_FILE);
+    let index = if index_path.exists() {
+      let content = fs::read_to_string(&index_path)
+        .map_err(|e| AgentError::Storage(format!("Failed to read cache index: {}", e)))?;
+      serde_json::from_str(&content).unwrap_or_else(|e| {
+        tracing::warn!("Discarding corrupt cache index: {}", e);
+        CacheIndex::default()
+      })
+    } else {
+      CacheIndex::default()
+    };
+
+    Ok(Self {
+      root,
+      settings: settings.clone(),
+      index,
+    })
+  }
+
+  /// Check whether caching is enabled
+  pub fn is_enabled(&self) -> bool {
+    self.settings.enabled
+  }
+
+  /// Check whether stale entries may be served when a source is unreachable
+  pub fn is_offline(&self) -> bool {
+    self.settings.offline
+  }
+
+  /// Get the cache directory
+  pub fn root(&self) -> &Path {
+    &self.root
+  }
+
+  /// Get the on-disk location for a key, which may hold a file or a directory
+  pub fn path_for(&self, key: &str) -> PathBuf {
+    self.root.join("entries").join(Self::hash(key.as_bytes()))
+  }
+
+  /// Look up a key, invalidating the entry when its source hash no longer matches
+  pub fn lookup(&mut self, key: &str, source_hash: Option<&str>) -> Result<CacheLookup> {
+    if !self.settings.enabled {
+      return Ok(CacheLookup::Miss);
+    }
+    let Some(entry) = self.index.entries.get(key).cloned() else {
+      return Ok(CacheLookup::Miss);
+    };
+
+    if !self.root.join(&entry.path).exists() {
+      self.index.entries.remove(key);
+      self.save_index()?;
+      return Ok(CacheLookup::Miss);
+    }
+
+    if let Some(hash) = source_hash
+      && hash != entry.source_hash
+    {
+      tracing::debug!("Invalidating cache entry {}: source changed", key);
+      self.remove(key)?;
+      return Ok(CacheLookup::Miss);
+    }
+
+    if self.is_expired(&entry, Self::now_secs()?) {
+      Ok(CacheLookup::Stale(entry))
+    } else {
+      Ok(CacheLookup::Fresh(entry))
+    }
+  }
+
+  /// Read a fresh blob entry, or a stale one when offline, marking it as recently used
+  pub fn get(&mut self, key: &str, source_hash: Option<&str>) -> Result<Option<Vec<u8>>> {
+    let offline = self.is_offline();
+    let entry = match self.lookup(key, source_hash)? {
+      CacheLookup::Fresh(entry) => entry,
+      CacheLookup::Stale(entry) if offline => entry,
+      CacheLookup::Stale(_) | CacheLookup::Miss => return Ok(None),
+    };
+    let content = fs::read(self.root.join(&entry.path))
+      .map_err(|e| AgentError::Storage(format!("Failed to read cache entry: {}", e)))?;
+    self.touch(key)?;
+    Ok(Some(content))
+  }
+
+  /// Store a blob entry
+  pub fn put(&mut self, key: &str, source_hash: &str, content: &[u8]) -> Result<()> {
+    if !self.settings.enabled {
+      return Ok(());
+    }
+    let path = self.path_for(key);
+    if let Some(parent) = path.parent() {
+      fs::create_dir_all(parent)
+        .map_err(|e| AgentError::Storage(format!("Failed to create cache directory: {}", e)))?;
+    }
+    fs::write(&path, content)
+      .map_err(|e| AgentError::Storage(format!("Failed to write cache entry: {}", e)))?;
+    self.insert(key, source_hash)
+  }
+
+  /// Record the content already present at `path_for(key)` as a cache entry
+  pub fn insert(&mut self, key: &str, source_hash: &str) -> Result<()> {
+    if !self.settings.enabled {
+      return Ok(());
+    }
+    let path = self.path_for(key);
+    let relative = path
+      .strip_prefix(&self.root)
+      .map_err(|_| AgentError::InvalidPath(path.display().to_string()))?
+      .to_string_lossy()
+      .replace('\\', "/");
+    let now = Self::now_secs()?;
+
+    self.index.entries.insert(
+      key.to_string(),
+      CacheEntry {
+        key: key.to_string(),
+        source_hash: source_hash.to_string(),
+        path: relative,
+        size_bytes: Self::size_of(&path),
+        created_at: now,
+        accessed_at: now,
+      },
+    );
+    self.evict(key)?;
+    self.save_index()
+  }
+
+  /// Mark an entry as recently used
+  pub fn touch(&mut self, key: &str) -> Result<()> {
+    let now = Self::now_secs()?;
+    if let Some(entry) = self.index.entries.get_mut(key) {
+      entry.accessed_at = now;
+      self.save_index()?;
+    }
+    Ok(())
+  }
+
+  /// Restart the TTL of an entry whose source was confirmed unchanged
+  pub fn revalidate(&mut self, key: &str) -> Result<()> {
+    let now = Self::now_secs()?;
+    if let Some(entry) = self.index.entries.get_mut(key) {
+      entry.created_at = now;
+      entry.accessed_at = now;
+      self.save_index()?;
+    }
+    Ok(())
+  }
+
+  /// Remove an entry and its content
+  pub fn remove(&mut self, key: &str) -> Result<()> {
+    if let Some(entry) = self.index.entries.remove(key) {
+      Self::remove_path(&self.root.join(&entry.path))?;
+      self.save_index()?;
+    }
+    Ok(())
+  }
+
+  /// Remove every entry, returning the statistics from before clearing
+  pub fn clear(&mut self) -> Result<CacheStats> {
+    let stats = self.stats()?;
+    let entries_dir = self.root.join("entries");
+    Self::remove_path(&entries_dir)?;
+    self.index.entries.clear();
+    self.save_index()?;
+    Ok(stats)
+  }
+
+  /// Compute cache statistics
+  pub fn stats(&self) -> Result<CacheStats> {
+    let now = Self::now_secs()?;
+    Ok(CacheStats {
+      enabled: self.settings.enabled,
+      directory: self.root.to_string_lossy().to_string(),
+      entries: self.index.entries.len(),
+      expired_entries: self
+        .index
+        .entries
+        .values()
+        .filter(|e| self.is_expired(e, now))
+        .count(),
+      total_size_bytes: self.index.entries.values().map(|e| e.size_bytes).sum(),
+      max_size_bytes: self.max_size_bytes(),
+    })
+  }
+
+  /// Compute a hex SHA-256 digest suitable as a source hash
+  pub fn hash(content: &[u8]) -> String {
+    Sha256::digest(content)
+      .iter()
+      .map(|b| format!("{:02x}", b))
+      .collect()
+  }
+
+  /// Evict least recently used entries until the cache fits its size limit
+  fn evict(&mut self, keep: &str) -> Result<()> {
+    let max_size = self.max_size_bytes();
+    let mut total: u64 = self.index.entries.values().map(|e| e.size_bytes).sum();
+    if total <= max_size {
+      return Ok(());
+    }
+
+    let mut candidates: Vec<CacheEntry> = self
+      .index
+      .entries
+      .values()
+      .filter(|e| e.key != keep)
+      .cloned()
+      .collect();
+    candidates.sort_by_key(|e| e.accessed_at);
+
+    for entry in candidates {
+      if total <= max_size {
+        break;
+      }
+      tracing::info!("Evicting cache entry {}", entry.key);
+      Self::remove_path(&self.root.join(&entry.path))?;
+      self.index.entries.remove(&entry.key);
+      total -= entry.size_bytes;
+    }
+    Ok(())
+  }
+
+  fn is_expired(&self, entry: &CacheEntry, now: u64) -> bool {
+    now.saturating_sub(entry.created_at) >= self.settings.ttl_seconds
+  }
+
+  fn max_size_bytes(&self) -> u64 {
+    self.settings.max_size_mb.saturating_mul(1024 * 1024)
+  }
+
+  fn save_index(&self) -> Result<()> {
+    fs::create_dir_all(&self.root)
+      .map_err(|e| AgentError::Storage(format!("Failed to create cache directory: {}", e)))?;
+    let content = serde_json::to_string_pretty(&self.index)
+      .map_err(|e| AgentError::Storage(format!("Failed to serialize cache index: {}", e)))?;
+    fs::write(self.root.join(INDEX_FILE), content)
+      .map_err(|e| AgentError::Storage(format!("Failed to write cache index: {}", e)))
+  }
+
+  fn size_of(path: &Path) -> u64 {
+    walkdir::WalkDir::new(path)
+      .into_iter()
+      .filter_map(|e| e.ok())
+      .filter_map(|e| e.metadata().ok())
+      .filter(|m| m.is_file())
+      .map(|m| m.len())
+      .sum()
+  }
+
+  fn remove_path(path: &Path) -> Result<()> {
+    let result = if path.is_dir() {
+      fs::remove_dir_all(path)
+    } else if path.exists() {
+      fs::remove_file(path)
+    } else {
+      return Ok(());
+    };
+    result.map_err(|e| AgentError::Storage(format!("Failed to remove cache entry: {}", e)))
+  }
+
+  fn now_secs() -> Result<u64> {
+    Ok(
+      SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map_err(|e| AgentError::Internal(format!("System time error: {}", e)))?
+        .as_secs(),
+    )
+  }
+}
+
+impl std::fmt::Display for CacheStats {
+  fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+    writeln!(f, "Directory: {}", self.directory)?;
+    writeln!(f, "Enabled: {}", self.enabled)?;
+    writeln!(
+      f,
+      "Entries: {} ({} expired)",
+      self.entries, self.expired_entries
+    )?;
+    write!(
+      f,
+      "Size: {:.2} MB / {:.2} MB",
+      self.total_size_bytes as f64 / (1024.0 * 1024.0),
+      self.max_size_bytes as f64 / (1024.0 * 1024.0)
+    )
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  fn settings(dir: &Path) -> CacheSettings {
+    CacheSettings {
+      directory: dir.to_string_lossy().to_string(),
+      ..CacheSettings::default()
+    }
+  }
+
+  #[test]
+  fn test_put_get_and_invalidate() {
+    let temp = tempfile::tempdir().unwrap();
+    let mut cache = TemplateCache::open(&settings(temp.path())).unwrap();
+
+    cache.put("compiled:a", "hash-1", b"content").unwrap();
+    assert_eq!(
+      cache.get("compiled:a", Some("hash-1")).unwrap(),
+      Some(b"content".to_vec())
+    );
+
+    // A changed source hash invalidates the entry
+    assert_eq!(cache.get("compiled:a", Some("hash-2")).unwrap(), None);
+    assert_eq!(cache.stats().unwrap().entries, 0);
+  }
+
+  #[test]
+  fn test_ttl_expiry_and_lru_eviction() {
+    let temp = tempfile::tempdir().unwrap();
+    let mut config = settings(temp.path());
+    config.ttl_seconds = 0;
+    config.max_size_mb = 1;
+    let mut cache = TemplateCache::open(&config).unwrap();
+
+    let half = vec![0u8; 600 * 1024];
+    cache.put("a", "h", &half).unwrap();
+    assert!(matches!(
+      cache.lookup("a", None).unwrap(),
+      CacheLookup::Stale(_)
+    ));
+
+    cache.put("b", "h", &half).unwrap();
+    let stats = cache.stats().unwrap();
+    assert_eq!(stats.entries, 1);
+    assert!(!cache.path_for("a").exists());
+    assert_eq!(cache.get("b", None).unwrap(), None);
+
+    // The index survives reopening
+    let reopened = TemplateCache::open(&config).unwrap();
+    assert_eq!(reopened.stats().unwrap().entries, 1);
+
+    // Stale entries are served offline
+    config.offline = true;
+    let mut offline = TemplateCache::open(&config).unwrap();
+    assert_eq!(offline.get("b", None).unwrap(), Some(half));
+  }
+}
@@ -0,0 +1,615 @@
+//! Prompt compilation for the agents core system
+//!
+//! Prompts are authored as `.src.md` sources and compiled into the sibling `.md`
+//! file the tools read, so `AGENTS.src.md` becomes `AGENTS.md`. Compiling a source:
+//!
+//! 1. splits off its front matter, taking the `variables` key out of it;
+//! 2. inlines `![[file]]` embeds, resolved relative to the embedding file;
+//! 3. renders the body with handlebars when it references partials or its front
+//!    matter declares `variables`, leaving tool placeholders such as `{{args}}`
+//!    of other sources untouched;
+//! 4. replaces `[[file|alias]]` links with their alias, or the file when unaliased;
+//! 5. passes the body through a [`Translator`].
+//!
+//! A manifest records the hash of every resolved source and of its output, so that
+//! sources whose input did not change and whose output was not edited are skipped.
+//! With a [`TemplateCache`], translated bodies are cached by their hash, so a
+//! forced or edited source is not translated again.
+
+use super::cache::TemplateCache;
+use super::config::PromptTemplate;
+use super::error::{AgentError, Result};
+use super::frontmatter;
+use super::storage;
+use super::template::TemplateRegistry;
+use regex::Regex;
+use serde::{Deserialize, Serialize};
+use std::collections::{BTreeMap, HashMap};
+use std::fmt;
+use std::path::{Path, PathBuf};
+use std::sync::LazyLock;
+
+/// Suffix of prompt sources
+pub const SOURCE_SUFFIX: &str = ".src.md";
+
+/// Front matter key holding the variables a source is rendered with
+const VARIABLES_KEY: &str = "variables";
+
+/// Matches Obsidian embeds, `![[file]]`, `![[file#heading]]` or `![[file|alias]]`
+static EMBED: LazyLock<Regex> =
+  LazyLock::new(|| Regex::new(r"!\[\[([^\]|#]+)(?:[|#][^\]]*)?\]\]").expect("valid embed regex"));
+
+/// Matches Obsidian links, `[[file]]` or `[[file|alias]]`
+static LINK: LazyLock<Regex> =
+  LazyLock::new(|| Regex::new(r"\[\[([^\]|]+)(?:\|([^\]]+))?\]\]").expect("valid link regex"));
+
+/// Transformation stage applied to the body of every compiled source
+pub trait Translator {
+  /// Identifier of the translator and its configuration, part of the source hash
+  fn id(&self) -> String;
+
+  /// Translate a Markdown body, keeping its structure and code intact
+  fn translate(&self, text: &str) -> Result<String>;
+}
+
+/// Translator leaving the text unchanged
+#[derive(Debug, Clone, Copy, Default)]
+pub struct NoopTranslator;
+
+impl Translator for NoopTranslator {
+  fn id(&self) -> String {
+    "noop".to_string()
+  }
+
+  fn translate(&self, text: &str) -> Result<String> {
+    Ok(text.to_string())
+  }
+}
+
+/// Translator replacing the terms of a dictionary, preferring the longest term
+#[derive(Debug, Clone, Default)]
+pub struct DictionaryTranslator {
+  entries: BTreeMap<String, String>,
+  pattern: Option<Regex>,
+}
+
+impl DictionaryTranslator {
+  /// Create a translator from term translations
+  pub fn new(entries: BTreeMap<String, String>) -> Result<Self> {
+    let mut terms: Vec<&String> = entries.keys().filter(|term| !term.is_empty()).collect();
+    terms.sort_by_key(|term| std::cmp::Reverse(term.chars().count()));
+    let pattern = if terms.is_empty() {
+      None
+    } else {
+      let alternation = terms
+        .iter()
+        .map(|term| regex::escape(term))
+        .collect::<Vec<_>>()
+        .join("|");
+      Some(
+        Regex::new(&alternation)
+          .map_err(|e| AgentError::Configuration(format!("Invalid dictionary: {}", e)))?,
+      )
+    };
+    Ok(Self { entries, pattern })
+  }
+
+  /// Load a JSON or TOML table of term translations
+  pub fn load<P: AsRef<Path>>(path: P) -> Result<Self> {
+    let path = path.as_ref();
+    let mut entries = BTreeMap::new();
+    for (term, value) in TemplateRegistry::load_variables(path)? {
+      let translation = value.as_str().ok_or_else(|| {
+        AgentError::Configuration(format!(
+          "{}: translation of '{}' must be a string",
+          path.display(),
+          term
+        ))
+      })?;
+      entries.insert(term, translation.to_string());
+    }
+    Self::new(entries)
+  }
+}
+
+impl Translator for DictionaryTranslator {
+  fn id(&self) -> String {
+    let entries = serde_json::to_vec(&self.entries).unwrap_or_default();
+    format!("dictionary:{}", TemplateCache::hash(&entries))
+  }
+
+  fn translate(&self, text: &str) -> Result<String> {
+    Ok(match &self.pattern {
+      Some(pattern) => pattern
+        .replace_all(text, |captures: &regex::Captures| {
+          self.entries[&captures[0]].clone()
+        })
+        .into_owned(),
+      None => text.to_string(),
+    })
+  }
+}
+
+/// Hashes of a compiled source and its output
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Eq)]
+pub struct ManifestEntry {
+  /// Hash of the resolved source and the translator
+  pub source_hash: String,
+  /// Compiled output path
+  pub output: String,
+  /// Hash of the written output
+  pub output_hash: String,
+}
+
+/// Source to output hashes of the compiled prompts, keyed by source path
+#[derive(Debug, Clone, Default, Serialize, Deserialize, PartialEq, Eq)]
+pub struct CompileManifest {
+  pub entries: BTreeMap<String, ManifestEntry>,
+}
+
+impl CompileManifest {
+  /// Load a manifest, starting empty when it is missing or corrupt
+  pub fn load<P: AsRef<Path>>(path: P) -> Result<Self> {
+    let path = path.as_ref();
+    if !path.exists() {
+      return Ok(Self::default());
+    }
+    let content = std::fs::read_to_string(path)
+      .map_err(|e| AgentError::Storage(format!("Failed to read compile manifest: {}", e)))?;
+    Ok(serde_json::from_str(&content).unwrap_or_else(|e| {
+      tracing::warn!("Discarding corrupt compile manifest: {}", e);
+      Self::default()
+    }))
+  }
+
+  /// Save the manifest atomically
+  pub fn save<P: AsRef<Path>>(&self, path: P) -> Result<()> {
+    let content = serde_json::to_string_pretty(self)
+      .map_err(|e| AgentError::Internal(format!("Failed to serialize compile manifest: {}", e)))?;
+    storage::write_atomic(path, content)
+  }
+}
+
+/// Outcome of compiling one source
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum CompileStatus {
+  /// The output was written
+  Compiled,
+  /// The source and output match the manifest, nothing was written
+  Unchanged,
+  /// The source could not be compiled
+  Failed(String),
+}
+
+/// Result of compiling one source
+#[derive(Debug, Clone)]
+pub struct CompileResult {
+  pub source: PathBuf,
+  pub output: PathBuf,
+  pub status: CompileStatus,
+}
+
+/// Results of compiling a set of sources
+#[derive(Debug, Clone, Default)]
+pub struct CompileReport {
+  pub results: Vec<CompileResult>,
+}
+
+impl CompileReport {
+  /// Check whether every source compiled
+  pub fn is_success(&self) -> bool {
+    !self
+      .results
+      .iter()
+      .any(|r| matches!(r.status, CompileStatus::Failed(_)))
+  }
+
+  fn count(&self, status: fn(&CompileStatus) -> bool) -> usize {
+    self.results.iter().filter(|r| status(&r.status)).count()
+  }
+}
+
+impl fmt::Display for CompileReport {
+  fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+    for result in &self.results {
+      let source = result.source.display();
+      match &result.status {
+        CompileStatus::Compiled => {
+          writeln!(f, "COMPILED  {} -> {}", source, result.output.display())?
+        }
+        CompileStatus::Unchanged => writeln!(f, "UNCHANGED {}", source)?,
+        CompileStatus::Failed(error) => writeln!(f, "FAILED    {}: {}", source, error)?,
+      }
+    }
+    write!(
+      f,
+      "{} compiled, {} unchanged, {} failed",
+      self.count(|s| *s == CompileStatus::Compiled),
+      self.count(|s| *s == CompileStatus::Unchanged),
+      self.count(|s| matches!(s, CompileStatus::Failed(_)))
+    )
+  }
+}
+
+/// Check whether a path is a prompt source
+pub fn is_source_file(path: &Path) -> bool {
+  path
+    .file_name()
+    .and_then(|s| s.to_str())
+    .is_some_and(|name| name.ends_with(SOURCE_SUFFIX))
+}
+
+/// Compiled sibling of a source, `name.src.md` becoming `name.md`
+pub fn output_path(source: &Path) -> Option<PathBuf> {
+  let name = source.file_name()?.to_str()?;
+  let stem = name.strip_suffix(SOURCE_SUFFIX)?;
+  Some(source.with_file_name(format!("{}.md", stem)))
+}
+
+/// Find the sources under files and directories, sorted and without duplicates
+pub fn discover<P: AsRef<Path>>(paths: &[P]) -> Vec<PathBuf> {
+  let mut sources: Vec<PathBuf> = paths
+    .iter()
+    .flat_map(|path| walkdir::WalkDir::new(path.as_ref()).into_iter())
+    .filter_map(|entry| entry.ok())
+    .filter(|entry| entry.file_type().is_file() && is_source_file(entry.path()))
+    .map(|entry| entry.into_path())
+    .collect();
+  sources.sort();
+  sources.dedup();
+  sources
+}
+
+/// Compiler of prompt sources
+pub struct Compiler<'a> {
+  registry: &'a TemplateRegistry,
+  translator: &'a dyn Translator,
+  cache: Option<TemplateCache>,
+}
+
+impl<'a> Compiler<'a> {
+  /// Create a compiler rendering with the partials of a registry
+  pub fn new(registry: &'a TemplateRegistry, translator: &'a dyn Translator) -> Self {
+    Self {
+      registry,
+      translator,
+      cache: None,
+    }
+  }
+
+  /// Cache translated bodies
+  pub fn with_cache(mut self, cache: TemplateCache) -> Self {
+    self.cache = Some(cache);
+    self
+  }
+
+  /// Compile sources, skipping those unchanged since the manifest was recorded
+  ///
+  /// With `force`, every source is compiled. The manifest is updated in place.
+  pub fn compile(
+    &mut self,
+    sources: &[PathBuf],
+    manifest: &mut CompileManifest,
+    force: bool,
+  ) -> CompileReport {
+    let mut report = CompileReport::default();
+    for source in sources {
+      let output = output_path(source).unwrap_or_else(|| source.clone());
+      let status = self
+        .compile_source(source, &output, manifest, force)
+        .unwrap_or_else(|e| CompileStatus::Failed(e.to_string()));
+      report.results.push(CompileResult {
+        source: source.clone(),
+        output,
+        status,
+      });
+    }
+    report
+  }
+
+  fn compile_source(
+    &mut self,
+    source: &Path,
+    output: &Path,
+    manifest: &mut CompileManifest,
+    force: bool,
+  ) -> Result<CompileStatus> {
+    if !is_source_file(source) {
+      return Err(AgentError::InvalidPath(format!(
+        "{} is not a {} source",
+        source.display(),
+        SOURCE_SUFFIX
+      )));
+    }
+
+    let (front_matter, body) = self.resolve(source)?;
+    let source_hash =
+      TemplateCache::hash(format!("{}\0{}{}", self.translator.id(), front_matter, body).as_bytes());
+
+    let key = source.to_string_lossy().to_string();
+    if !force
+      && let Some(entry) = manifest.entries.get(&key)
+      && entry.source_hash == source_hash
+      && entry.output == output.to_string_lossy()
+      && std::fs::read(output)
+        .is_ok_and(|written| TemplateCache::hash(&written) == entry.output_hash)
+    {
+      return Ok(CompileStatus::Unchanged);
+    }
+
+    let compiled = format!("{}{}", front_matter, self.translate(&body)?);
+    storage::write_atomic(output, compiled.as_bytes())?;
+    manifest.entries.insert(
+      key,
+      ManifestEntry {
+        source_hash,
+        output: output.to_string_lossy().to_string(),
+        output_hash: TemplateCache::hash(compiled.as_bytes()),
+      },
+    );
+    Ok(CompileStatus::Compiled)
+  }
+
+  /// Translate a body, through the cache when there is one
+  fn translate(&mut self, body: &str) -> Result<String> {
+    let Some(cache) = self.cache.as_mut() else {
+      return self.translator.translate(body);
+    };
+    let hash = TemplateCache::hash(format!("{}\0{}", self.translator.id(), body).as_bytes());
+    let key = format!("translated:{}", hash);
+    if let Some(cached) = cache.get(&key, Some(&hash))?
+      && let Ok(translated) = String::from_utf8(cached)
+    {
+      return Ok(translated);
+    }
+    let translated = self.translator.translate(body)?;
+    cache.put(&key, &hash, translated.as_bytes())?;
+    Ok(translated)
+  }
+
+  /// Resolve a source into its rendered front matter block and untranslated body
+  fn resolve(&self, source: &Path) -> Result<(String, String)> {
+    let content = read(source)?;
+    let (mut front_matter, body) = match frontmatter::split(&content) {
+      Some(_) => frontmatter::parse::<serde_norway::Mapping>(&content)?,
+      None => (serde_norway::Mapping::new(), content.clone()),
+    };
+
+    let variables: Option<HashMap<String, serde_json::Value>> = front_matter
+      .remove(VARIABLES_KEY)
+      .map(serde_norway::from_value)
+      .transpose()?;
+
+    let mut stack = vec![canonical(source)];
+    let mut body = inline_embeds(source, &body, &mut stack)?;
+
+    if variables.is_some() || TemplateRegistry::uses_partials(&body) {
+      let template = PromptTemplate {
+        name: source.to_string_lossy().to_string(),
+        content: body,
+        ..PromptTemplate::default()
+      };
+      body = self
+        .registry
+        .render_source(&template, &variables.unwrap_or_default())?;
+    }
+
+    let body = LINK
+      .replace_all(&body, |captures: &regex::Captures| {
+        captures
+          .get(2)
+          .unwrap_or_else(|| captures.get(1).expect("link target"))
+          .as_str()
+          .trim()
+          .to_string()
+      })
+      .into_owned();
+
+    let front_matter = if front_matter.is_empty() {
+      String::new()
+    } else {
+      frontmatter::render(&front_matter, "")?
+    };
+    Ok((front_matter, body))
+  }
+}
+
+/// Inline the embeds of a file, refusing embeds that include themselves
+fn inline_embeds(file: &Path, content: &str, stack: &mut Vec<PathBuf>) -> Result<String> {
+  let mut inlined = String::with_capacity(content.len());
+  let mut last = 0;
+  for captures in EMBED.captures_iter(content) {
+    let whole = captures.get(0).expect("embed match");
+    let target = resolve_embed(file, captures[1].trim())?;
+    let canonical_target = canonical(&target);
+    if stack.contains(&canonical_target) {
+      return Err(AgentError::Template(format!(
+        "{} embeds itself through {}",
+        target.display(),
+        file.display()
+      )));
+    }
+
+    let embedded = read(&target)?;
+    let embedded = match frontmatter::split(&embedded) {
+      Some((_, body)) => body.to_string(),
+      None => embedded,
+    };
+    stack.push(canonical_target);
+    let embedded = inline_embeds(&target, &embedded, stack)?;
+    stack.pop();
+
+    inlined.push_str(&content[last..whole.start()]);
+    inlined.push_str(embedded.trim_end_matches('\n'));
+    last = whole.end();
+  }
+  inlined.push_str(&content[last..]);
+  Ok(inlined)
+}
+
+/// Locate the file of an embed, preferring the source of a target without extension
+fn resolve_embed(file: &Path, target: &str) -> Result<PathBuf> {
+  let base = file.parent().unwrap_or(Path::new("")).join(target);
+  let candidates = if base.extension().is_some() {
+    vec![base.clone()]
+  } else {
+    vec![
+      base.with_file_name(format!("{}{}", target_name(&base), SOURCE_SUFFIX)),
+      base.with_file_name(format!("{}.md", target_name(&base))),
+    ]
+  };
+  candidates
+    .into_iter()
+    .find(|candidate| candidate.is_file())
+    .ok_or_else(|| {
+      AgentError::FileNotFound(format!("{} embedded by {}", base.display(), file.display()))
+    })
+}
+
+fn target_name(path: &Path) -> String {
+  path
+    .file_name()
+    .map(|name| name.to_string_lossy().to_string())
+    .unwrap_or_default()
+}
+
+fn canonical(path: &Path) -> PathBuf {
+  path.canonicalize().unwrap_or_else(|_| path.to_path_buf())
+}
+
+fn read(path: &Path) -> Result<String> {
+  std::fs::read_to_string(path)
+    .map_err(|e| AgentError::FileNotFound(format!("{}: {}", path.display(), e)))
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  #[test]
+  fn test_compile_resolves_embeds_templates_and_links() {
+    let temp = tempfile::tempdir().unwrap();
+    let dir = temp.path();
+    std::fs::write(
+      dir.join("AGENTS.src.md"),
+      "---\ndescription: 项目说明\nvariables:\n  project: demo\n---\n# {{project}}\n\n![[shared/rules]]\n\n参见 [[guide|指南]] 和 [[setup]]\n",
+    )
+    .unwrap();
+    std::fs::create_dir_all(dir.join("shared")).unwrap();
+    std::fs::write(
+      dir.join("shared/rules.src.md"),
+      "---\nkind: rule\n---\n- 使用中文\n",
+    )
+    .unwrap();
+
+    let registry = TemplateRegistry::new().unwrap();
+    let translator = DictionaryTranslator::new(BTreeMap::from([
+      ("使用中文".to_string(), "Use English".to_string()),
+      ("中文".to_string(), "Chinese".to_string()),
+      ("参见".to_string(), "See".to_string()),
+      ("和".to_string(), "and".to_string()),
+      ("指南".to_string(), "guide".to_string()),
+    ]))
+    .unwrap();
+    let mut compiler = Compiler::new(&registry, &translator);
+    let mut manifest = CompileManifest::default();
+
+    let sources = discover(&[dir]);
+    assert_eq!(sources.len(), 2);
+    let report = compiler.compile(&[dir.join("AGENTS.src.md")], &mut manifest, false);
+    assert!(report.is_success(), "{}", report);
+    assert_eq!(
+      std::fs::read_to_string(dir.join("AGENTS.md")).unwrap(),
+      "---\ndescription: 项目说明\n---\n# demo\n\n- Use English\n\nSee guide and setup\n"
+    );
+
+    // Unchanged sources are skipped until the output is edited or forced
+    let report = compiler.compile(&[dir.join("AGENTS.src.md")], &mut manifest, false);
+    assert_eq!(report.results[0].status, CompileStatus::Unchanged);
+    std::fs::write(dir.join("AGENTS.md"), "edited").unwrap();
+    let report = compiler.compile(&[dir.join("AGENTS.src.md")], &mut manifest, false);
+    assert_eq!(report.results[0].status, CompileStatus::Compiled);
+
+    // Embedded files count as part of the source
+    std::fs::write(dir.join("shared/rules.src.md"), "- 中文\n").unwrap();
+    let report = compiler.compile(&[dir.join("AGENTS.src.md")], &mut manifest, false);
+    assert_eq!(report.results[0].status, CompileStatus::Compiled);
+    assert!(
+      std::fs::read_to_string(dir.join("AGENTS.md"))
+        .unwrap()
+        .contains("- Chinese\n")
+    );
+  }
+
+  /// Translator counting its calls
+  struct CountingTranslator(std::cell::Cell<usize>);
+
+  impl Translator for CountingTranslator {
+    fn id(&self) -> String {
+      "counting".to_string()
+    }
+
+    fn translate(&self, text: &str) -> Result<String> {
+      self.0.set(self.0.get() + 1);
+      Ok(text.to_uppercase())
+    }
+  }
+
+  #[test]
+  fn test_translations_are_cached() {
+    let temp = tempfile::tempdir().unwrap();
+    let source = temp.path().join("notes.src.md");
+    std::fs::write(&source, "notes\n").unwrap();
+    let cache = TemplateCache::open(&crate::modules::config::CacheSettings {
+      directory: temp.path().join("cache").to_string_lossy().to_string(),
+      ..Default::default()
+    })
+    .unwrap();
+
+    let registry = TemplateRegistry::new().unwrap();
+    let translator = CountingTranslator(std::cell::Cell::new(0));
+    let mut compiler = Compiler::new(&registry, &translator).with_cache(cache);
+    let mut manifest = CompileManifest::default();
+    compiler.compile(std::slice::from_ref(&source), &mut manifest, false);
+    let report = compiler.compile(std::slice::from_ref(&source), &mut manifest, true);
+
+    assert_eq!(report.results[0].status, CompileStatus::Compiled);
+    assert_eq!(
+      std::fs::read_to_string(temp.path().join("notes.md")).unwrap(),
+      "NOTES\n"
+    );
+    assert_eq!(translator.0.get(), 1);
+  }
+
+  #[test]
+  fn test_tool_placeholders_are_kept_without_variables() {
+    let temp = tempfile::tempdir().unwrap();
+    let source = temp.path().join("review.src.md");
+    std::fs::write(&source, "Review {{args}} and $ARGUMENTS\n").unwrap();
+
+    let registry = TemplateRegistry::new().unwrap();
+    let mut compiler = Compiler::new(&registry, &NoopTranslator);
+    let report = compiler.compile(&[source], &mut CompileManifest::default(), false);
+    assert!(report.is_success(), "{}", report);
+    assert_eq!(
+      std::fs::read_to_string(temp.path().join("review.md")).unwrap(),
+      "Review {{args}} and $ARGUMENTS\n"
+    );
+  }
+
+  #[test]
+  fn test_self_embedding_sources_fail() {
+    let temp = tempfile::tempdir().unwrap();
+    let source = temp.path().join("loop.src.md");
+    std::fs::write(&source, "![[loop]]\n").unwrap();
+
+    let registry = TemplateRegistry::new().unwrap();
+    let mut compiler = Compiler::new(&registry, &NoopTranslator);
+    let report = compiler.compile(&[source], &mut CompileManifest::default(), false);
+    assert!(matches!(
+      &report.results[0].status,
+      CompileStatus::Failed(error) if error.contains("embeds itself")
+    ));
+    assert!(!temp.path().join("loop.md").exists());
+  }
+}
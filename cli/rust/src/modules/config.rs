@@ -4,7 +4,7 @@
 
 use super::error::{AgentError, Result};
 use serde::{Deserialize, Serialize};
-use std::collections::HashMap;
+use std::collections::{BTreeMap, HashMap};
 
 /// Main configuration structure for the agents system
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -17,6 +17,9 @@ pub struct AgentConfig {
   pub templates: TemplateSettings,
   /// Platform-specific settings
   pub platform: PlatformSettings,
+  /// Prompt distribution settings
+  #[serde(default)]
+  pub sync: SyncSettings,
 }
 
 /// Directory mappings for different types of prompts
@@ -32,6 +35,12 @@ pub struct DirectoryMappings {
   pub sub_agent: String,
   /// Command prompts directory
   pub command: String,
+  /// Skills directory
+  #[serde(default = "default_skill_directory")]
+  pub skill: String,
+  /// Rules directory
+  #[serde(default = "default_rule_directory")]
+  pub rule: String,
   /// Backups directory
   pub backups: String,
 }
@@ -41,10 +50,109 @@ pub struct DirectoryMappings {
 pub struct TemplateSettings {
   /// Default template repository URL
   pub repository: Option<String>,
+  /// Git reference (branch, tag or commit) the default repository is pinned to
+  #[serde(default)]
+  pub reference: Option<String>,
+  /// Additional remote template packs
+  #[serde(default)]
+  pub packs: Vec<TemplatePack>,
   /// Local templates directory
   pub local_directory: String,
+  /// User templates directory, overriding project templates; defaults to
+  /// `templates` under the platform configuration directory
+  #[serde(default)]
+  pub user_directory: Option<String>,
   /// Template cache settings
   pub cache: CacheSettings,
+  /// Template helper settings
+  #[serde(default)]
+  pub helpers: HelperSettings,
+}
+
+/// Template helper settings
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct HelperSettings {
+  /// Environment variables readable through the `env` helper
+  #[serde(default)]
+  pub env_allowlist: Vec<String>,
+  /// Project-defined helpers
+  #[serde(default)]
+  pub rules: Vec<HelperRule>,
+}
+
+/// Declarative project helper transforming its first parameter
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct HelperRule {
+  /// Helper name
+  pub name: String,
+  /// Helper description
+  #[serde(default)]
+  pub description: String,
+  /// Regex replacements applied in order
+  #[serde(default)]
+  pub replace: Vec<HelperReplacement>,
+  /// Case conversion applied after the replacements
+  #[serde(default)]
+  pub case: Option<HelperCase>,
+  /// Text prepended to the result
+  #[serde(default)]
+  pub prefix: String,
+  /// Text appended to the result
+  #[serde(default)]
+  pub suffix: String,
+}
+
+/// Regex replacement of a helper rule
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct HelperReplacement {
+  /// Regular expression to match
+  pub pattern: String,
+  /// Replacement text, supporting `$1` style capture references
+  pub replacement: String,
+}
+
+/// Case conversion of a helper rule
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq, Eq)]
+#[serde(rename_all = "lowercase")]
+pub enum HelperCase {
+  Upper,
+  Lower,
+}
+
+/// Remote template pack fetched from a git repository
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Eq)]
+pub struct TemplatePack {
+  /// Git URL of the pack (remote URL, `file://` URL or local bare repository path)
+  pub url: String,
+  /// Git reference (branch, tag or commit) the pack is pinned to
+  #[serde(default)]
+  pub reference: Option<String>,
+  /// Namespace prefix applied to every template name of the pack
+  pub namespace: String,
+  /// Subdirectory of the repository containing the templates
+  #[serde(default)]
+  pub path: Option<String>,
+}
+
+impl TemplatePack {
+  /// Check that the URL and reference cannot be taken for git options
+  pub fn validate(&self) -> Result<()> {
+    if self.url.starts_with('-') {
+      return Err(AgentError::Configuration(format!(
+        "Template pack URL '{}' must not start with '-'",
+        self.url
+      )));
+    }
+    if let Some(reference) = &self.reference
+      && reference.starts_with('-')
+    {
+      return Err(AgentError::Configuration(format!(
+        "Template pack reference '{}' of {} must not start with '-'",
+        reference, self.url
+      )));
+    }
+    Ok(())
+  }
 }
 
 /// Cache configuration
@@ -58,6 +166,9 @@ pub struct CacheSettings {
   pub max_size_mb: u64,
   /// Cache TTL in seconds
   pub ttl_seconds: u64,
+  /// Serve stale entries when a source is unreachable
+  #[serde(default)]
+  pub offline: bool,
 }
 
 /// Platform-specific settings
@@ -71,6 +182,40 @@ pub struct PlatformSettings {
   pub linux: Option<PlatformSpecific>,
 }
 
+/// Prompt distribution settings
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct SyncSettings {
+  /// Sync targets enabled for the project, by identifier
+  #[serde(default)]
+  pub targets: Vec<String>,
+  /// Settings of the targets, by identifier
+  #[serde(default)]
+  pub settings: BTreeMap<String, TargetSettings>,
+}
+
+impl SyncSettings {
+  /// Get the settings of a target, the defaults when it has none
+  pub fn settings_for(&self, target: &str) -> TargetSettings {
+    self.settings.get(target).cloned().unwrap_or_default()
+  }
+}
+
+/// Settings of one sync target
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct TargetSettings {
+  /// Maximum size of a generated file in characters, for targets that split or
+  /// check their files
+  #[serde(default)]
+  pub max_size: Option<usize>,
+  /// Prompts the target gets, by `<type>/<path>` pattern such as `rule/*`; all
+  /// when empty
+  #[serde(default)]
+  pub include: Vec<String>,
+  /// Prompts the target never gets, by `<type>/<path>` pattern
+  #[serde(default)]
+  pub exclude: Vec<String>,
+}
+
 /// Platform-specific configuration
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct PlatformSpecific {
@@ -99,6 +244,15 @@ pub struct PromptTemplate {
   pub metadata: HashMap<String, String>,
   /// Required variables for the template
   pub variables: Vec<TemplateVariable>,
+  /// Name of the parent template this template extends
+  #[serde(default)]
+  pub extends: Option<String>,
+  /// Named blocks overriding the parent's `{{#> block}}...{{/block}}` sections
+  #[serde(default)]
+  pub blocks: BTreeMap<String, String>,
+  /// Whether this is the template composed by default for its type
+  #[serde(default, skip_serializing_if = "std::ops::Not::not")]
+  pub default: bool,
 }
 
 /// Template variable definition
@@ -107,15 +261,22 @@ pub struct TemplateVariable {
   /// Variable name
   pub name: String,
   /// Variable description
+  #[serde(default)]
   pub description: String,
   /// Variable type
   pub r#type: VariableType,
   /// Whether the variable is required
+  #[serde(default)]
   pub required: bool,
   /// Default value
+  #[serde(default, skip_serializing_if = "Option::is_none")]
   pub default: Option<String>,
   /// Possible values (for enums)
+  #[serde(default, skip_serializing_if = "Option::is_none")]
   pub values: Option<Vec<String>>,
+  /// Whether the value is long-form text, edited in an external editor when composing
+  #[serde(default)]
+  pub multiline: bool,
 }
 
 /// Template variable types
@@ -130,12 +291,165 @@ pub enum VariableType {
 }
 
 /// Template types
+///
+/// Older configurations used `memory` for locale memories and `subagent` for
+/// sub-agents; both are still accepted.
 #[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Eq)]
-#[serde(rename_all = "lowercase")]
+#[serde(rename_all = "kebab-case")]
 pub enum TemplateType {
-  Memory,
+  /// Memory shared by all projects of a user
+  UserMemory,
+  /// Memory of a project, shared by its contributors
+  ProjectMemory,
+  /// Localized memory prompts
+  #[serde(alias = "memory")]
+  LocaleMemory,
+  #[serde(alias = "subagent")]
   SubAgent,
   Command,
+  /// Skill with its instructions and assets
+  Skill,
+  /// Rule applied by agents while working on the project
+  Rule,
+}
+
+impl std::fmt::Display for TemplateType {
+  fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+    let name = match self {
+      TemplateType::UserMemory => "user-memory",
+      TemplateType::ProjectMemory => "project-memory",
+      TemplateType::LocaleMemory => "locale-memory",
+      TemplateType::SubAgent => "sub-agent",
+      TemplateType::Command => "command",
+      TemplateType::Skill => "skill",
+      TemplateType::Rule => "rule",
+    };
+    write!(f, "{}", name)
+  }
+}
+
+impl TemplateVariable {
+  /// Parse the string default into a value of the declared type
+  pub fn default_value(&self) -> std::result::Result<Option<serde_json::Value>, String> {
+    self
+      .default
+      .as_deref()
+      .map(|raw| self.r#type.parse(raw))
+      .transpose()
+  }
+
+  /// Coerce a value to the declared type and check it against the allowed values
+  ///
+  /// A null value of an optional variable stands for its default, or stays null
+  /// without one.
+  pub fn coerce(
+    &self,
+    value: &serde_json::Value,
+  ) -> std::result::Result<serde_json::Value, String> {
+    if value.is_null() && !self.required {
+      return match self.default_value()? {
+        Some(default) => self.coerce(&default),
+        None => Ok(serde_json::Value::Null),
+      };
+    }
+    let value = self.r#type.coerce(value)?;
+    if let Some(allowed) = &self.values {
+      let items = match &value {
+        serde_json::Value::Array(items) => items.clone(),
+        other => vec![other.clone()],
+      };
+      for item in items {
+        let text = match &item {
+          serde_json::Value::String(s) => s.clone(),
+          other => other.to_string(),
+        };
+        if !allowed.contains(&text) {
+          return Err(format!(
+            "value '{}' is not one of [{}]",
+            text,
+            allowed.join(", ")
+          ));
+        }
+      }
+    }
+    Ok(value)
+  }
+}
+
+impl VariableType {
+  /// Parse a raw string into a value of this type
+  pub fn parse(&self, raw: &str) -> std::result::Result<serde_json::Value, String> {
+    match self {
+      Self::String => Ok(serde_json::Value::String(raw.to_string())),
+      Self::Array if !raw.trim_start().starts_with('[') => Ok(serde_json::Value::Array(
+        raw
+          .split(',')
+          .map(|item| item.trim())
+          .filter(|item| !item.is_empty())
+          .map(|item| serde_json::Value::String(item.to_string()))
+          .collect(),
+      )),
+      Self::Array | Self::Object => {
+        let value: serde_json::Value =
+          serde_json::from_str(raw).map_err(|e| format!("'{}' is not valid JSON: {}", raw, e))?;
+        self.coerce(&value)
+      }
+      Self::Number | Self::Boolean => self.coerce(&serde_json::Value::String(raw.to_string())),
+    }
+  }
+
+  /// Coerce a value to this type, converting compatible representations
+  pub fn coerce(
+    &self,
+    value: &serde_json::Value,
+  ) -> std::result::Result<serde_json::Value, String> {
+    use serde_json::Value;
+
+    let mismatch = || format!("expected {}, got {}", self.name(), value);
+    match (self, value) {
+      (Self::String, Value::String(_)) => Ok(value.clone()),
+      (Self::String, Value::Number(n)) => Ok(Value::String(n.to_string())),
+      (Self::String, Value::Bool(b)) => Ok(Value::String(b.to_string())),
+      (Self::Number, Value::Number(_)) => Ok(value.clone()),
+      (Self::Number, Value::String(s)) => {
+        let s = s.trim();
+        if let Ok(i) = s.parse::<i64>() {
+          Ok(Value::from(i))
+        } else {
+          s.parse::<f64>()
+            .ok()
+            .and_then(serde_json::Number::from_f64)
+            .map(Value::Number)
+            .ok_or_else(mismatch)
+        }
+      }
+      (Self::Boolean, Value::Bool(_)) => Ok(value.clone()),
+      (Self::Boolean, Value::String(s)) => match s.trim().to_lowercase().as_str() {
+        "true" | "yes" | "on" | "1" => Ok(Value::Bool(true)),
+        "false" | "no" | "off" | "0" => Ok(Value::Bool(false)),
+        _ => Err(mismatch()),
+      },
+      (Self::Boolean, Value::Number(n)) if n.as_i64() == Some(0) || n.as_i64() == Some(1) => {
+        Ok(Value::Bool(n.as_i64() == Some(1)))
+      }
+      (Self::Array, Value::Array(_)) => Ok(value.clone()),
+      (Self::Array, Value::String(s)) => self.parse(s),
+      (Self::Object, Value::Object(_)) => Ok(value.clone()),
+      (Self::Object, Value::String(s)) if s.trim_start().starts_with('{') => self.parse(s),
+      _ => Err(mismatch()),
+    }
+  }
+
+  /// Lowercase name of the type
+  pub fn name(&self) -> &'static str {
+    match self {
+      Self::String => "string",
+      Self::Number => "number",
+      Self::Boolean => "boolean",
+      Self::Array => "array",
+      Self::Object => "object",
+    }
+  }
 }
 
 impl Default for AgentConfig {
@@ -145,6 +459,7 @@ impl Default for AgentConfig {
       directories: DirectoryMappings::default(),
       templates: TemplateSettings::default(),
       platform: PlatformSettings::default(),
+      sync: SyncSettings::default(),
     }
   }
 }
@@ -157,18 +472,67 @@ impl Default for DirectoryMappings {
       project: "__ai/project".to_string(),
       sub_agent: "__ai/sa".to_string(),
       command: "__ai/cmd".to_string(),
+      skill: default_skill_directory(),
+      rule: default_rule_directory(),
       backups: ".agents/backups".to_string(),
     }
   }
 }
 
+fn default_skill_directory() -> String {
+  "__ai/skills".to_string()
+}
+
+fn default_rule_directory() -> String {
+  "__ai/rules".to_string()
+}
+
+impl DirectoryMappings {
+  /// Get the directory prompts of a template type are written to
+  pub fn directory_for(&self, template_type: &TemplateType) -> &str {
+    match template_type {
+      TemplateType::UserMemory => &self.user,
+      TemplateType::ProjectMemory => &self.project,
+      TemplateType::LocaleMemory => &self.memory,
+      TemplateType::SubAgent => &self.sub_agent,
+      TemplateType::Command => &self.command,
+      TemplateType::Skill => &self.skill,
+      TemplateType::Rule => &self.rule,
+    }
+  }
+}
+
 impl Default for TemplateSettings {
   fn default() -> Self {
     Self {
       repository: None,
+      reference: None,
+      packs: Vec::new(),
       local_directory: "__ai/templates".to_string(),
+      user_directory: None,
       cache: CacheSettings::default(),
+      helpers: HelperSettings::default(),
+    }
+  }
+}
+
+impl TemplateSettings {
+  /// Namespace used for templates of the default repository
+  pub const DEFAULT_NAMESPACE: &'static str = "remote";
+
+  /// All configured remote packs, including the default repository
+  pub fn remote_packs(&self) -> Vec<TemplatePack> {
+    let mut packs = Vec::new();
+    if let Some(url) = &self.repository {
+      packs.push(TemplatePack {
+        url: url.clone(),
+        reference: self.reference.clone(),
+        namespace: Self::DEFAULT_NAMESPACE.to_string(),
+        path: None,
+      });
     }
+    packs.extend(self.packs.iter().cloned());
+    packs
   }
 }
 
@@ -179,6 +543,7 @@ impl Default for CacheSettings {
       directory: ".agents/cache".to_string(),
       max_size_mb: 100,
       ttl_seconds: 3600,
+      offline: false,
     }
   }
 }
@@ -211,10 +576,13 @@ impl Default for PromptTemplate {
       name: "default".to_string(),
       description: "Default template".to_string(),
       version: "1.0.0".to_string(),
-      r#type: TemplateType::Memory,
+      r#type: TemplateType::LocaleMemory,
       content: "".to_string(),
       metadata: HashMap::new(),
       variables: Vec::new(),
+      extends: None,
+      blocks: BTreeMap::new(),
+      default: false,
     }
   }
 }
@@ -237,8 +605,12 @@ impl ConfigManager {
     let content = std::fs::read_to_string(&self.config_path)
       .map_err(|e| AgentError::FileNotFound(format!("{}: {}", self.config_path, e)))?;
 
-    serde_json::from_str(&content)
-      .map_err(|e| AgentError::Configuration(format!("Failed to parse config: {}", e)))
+    let config: AgentConfig = serde_json::from_str(&content)
+      .map_err(|e| AgentError::Configuration(format!("Failed to parse config: {}", e)))?;
+    for pack in config.templates.remote_packs() {
+      pack.validate()?;
+    }
+    Ok(config)
   }
 
   /// Save configuration to file
@@ -261,3 +633,78 @@ impl ConfigManager {
     &self.config_path
   }
 }
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  #[test]
+  fn test_optional_variables_accept_null() {
+    let mut variable = TemplateVariable {
+      name: "level".to_string(),
+      description: String::new(),
+      r#type: VariableType::Number,
+      required: false,
+      default: Some("2".to_string()),
+      values: None,
+      multiline: false,
+    };
+    let null = serde_json::Value::Null;
+    assert_eq!(variable.coerce(&null), Ok(serde_json::json!(2)));
+
+    variable.default = None;
+    assert_eq!(variable.coerce(&null), Ok(null.clone()));
+
+    variable.required = true;
+    assert!(variable.coerce(&null).is_err());
+  }
+
+  #[test]
+  fn test_template_types_accept_legacy_names() {
+    let types: Vec<TemplateType> =
+      serde_json::from_str(r#"["memory", "subagent", "locale-memory", "user-memory", "rule"]"#)
+        .unwrap();
+    assert_eq!(
+      types,
+      vec![
+        TemplateType::LocaleMemory,
+        TemplateType::SubAgent,
+        TemplateType::LocaleMemory,
+        TemplateType::UserMemory,
+        TemplateType::Rule,
+      ]
+    );
+    assert_eq!(
+      serde_json::to_string(&TemplateType::SubAgent).unwrap(),
+      r#""sub-agent""#
+    );
+
+    let directories = DirectoryMappings::default();
+    assert_eq!(
+      directories.directory_for(&TemplateType::Skill),
+      "__ai/skills"
+    );
+    assert_eq!(directories.directory_for(&TemplateType::Rule), "__ai/rules");
+  }
+
+  #[test]
+  fn test_pack_options_are_rejected() {
+    let temp = tempfile::tempdir().unwrap();
+    let path = temp.path().join("agents.prompts.json");
+    let mut config = AgentConfig::default();
+    config.templates.packs.push(TemplatePack {
+      url: "--upload-pack=touch /tmp/pwned".to_string(),
+      reference: None,
+      namespace: "team".to_string(),
+      path: None,
+    });
+    let manager = ConfigManager::new(path.to_string_lossy());
+    manager.save(&config).unwrap();
+    assert!(manager.load().is_err());
+
+    config.templates.packs[0].url = "https://example.com/pack.git".to_string();
+    config.templates.packs[0].reference = Some("--output=x".to_string());
+    manager.save(&config).unwrap();
+    assert!(manager.load().is_err());
+  }
+}
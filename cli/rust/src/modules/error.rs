@@ -2,6 +2,7 @@
 //!
 //! This module provides centralized error types and handling for the agents core.
 
+use serde::{Deserialize, Serialize};
 use thiserror::Error;
 
 /// Main error type for the agents core system
@@ -16,15 +17,24 @@ pub enum AgentError {
   #[error("TOML parsing error: {0}")]
   TomlParsing(#[from] toml::de::Error),
 
+  #[error("YAML error: {0}")]
+  Yaml(#[from] serde_norway::Error),
+
   #[error("Configuration error: {0}")]
   Configuration(String),
 
   #[error("Template error: {0}")]
   Template(String),
 
+  #[error("Invalid template variables: {}", format_violations(.0))]
+  InvalidVariables(Vec<VariableViolation>),
+
   #[error("Storage error: {0}")]
   Storage(String),
 
+  #[error("Remote repository error: {0}")]
+  Remote(String),
+
   #[error("Platform detection error: {0}")]
   Platform(String),
 
@@ -44,6 +54,29 @@ pub enum AgentError {
   Internal(String),
 }
 
+/// A template variable that failed validation
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct VariableViolation {
+  /// Variable name
+  pub variable: String,
+  /// Description of the violation
+  pub message: String,
+}
+
+impl std::fmt::Display for VariableViolation {
+  fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+    write!(f, "'{}': {}", self.variable, self.message)
+  }
+}
+
+fn format_violations(violations: &[VariableViolation]) -> String {
+  violations
+    .iter()
+    .map(|v| v.to_string())
+    .collect::<Vec<_>>()
+    .join("; ")
+}
+
 /// Result type alias for convenience
 pub type Result<T> = std::result::Result<T, AgentError>;
 
@@ -63,6 +96,11 @@ impl AgentError {
     Self::Storage(msg.into())
   }
 
+  /// Create a new remote repository error
+  pub fn remote<S: Into<String>>(msg: S) -> Self {
+    Self::Remote(msg.into())
+  }
+
   /// Create a new platform error
   pub fn platform<S: Into<String>>(msg: S) -> Self {
     Self::Platform(msg.into())
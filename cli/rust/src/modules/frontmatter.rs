@@ -0,0 +1,70 @@
+//! Markdown front matter for the agents core system
+//!
+//! This module splits and renders Markdown documents with a leading YAML front matter
+//! block delimited by `---` lines, the format used by prompts across the repository.
+
+use super::error::{AgentError, Result};
+use serde::Serialize;
+use serde::de::DeserializeOwned;
+
+/// Front matter delimiter line
+const DELIMITER: &str = "---";
+
+/// Split a document into its raw YAML front matter and body
+///
+/// Returns `None` when the document does not start with a front matter block.
+pub fn split(content: &str) -> Option<(&str, &str)> {
+  let rest = content
+    .strip_prefix("---\r\n")
+    .or_else(|| content.strip_prefix("---\n"))?;
+
+  let mut offset = 0;
+  for line in rest.split_inclusive('\n') {
+    if line.trim_end_matches(['\r', '\n']) == DELIMITER {
+      return Some((&rest[..offset], &rest[offset + line.len()..]));
+    }
+    offset += line.len();
+  }
+  // A closing delimiter on the last line without a trailing newline
+  (rest[offset..] == *DELIMITER).then(|| (&rest[..offset], ""))
+}
+
+/// Parse the front matter of a document into `T`, returning it with the body
+pub fn parse<T: DeserializeOwned>(content: &str) -> Result<(T, String)> {
+  let (yaml, body) =
+    split(content).ok_or_else(|| AgentError::Template("Missing front matter block".to_string()))?;
+  let meta = if yaml.trim().is_empty() {
+    serde_norway::from_str("{}")?
+  } else {
+    serde_norway::from_str(yaml)?
+  };
+  Ok((meta, body.to_string()))
+}
+
+/// Render a document from front matter and a body
+pub fn render<T: Serialize>(meta: &T, body: &str) -> Result<String> {
+  let yaml = serde_norway::to_string(meta)?;
+  Ok(format!("{}\n{}{}\n{}", DELIMITER, yaml, DELIMITER, body))
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+  use std::collections::BTreeMap;
+
+  #[test]
+  fn test_split_and_render_round_trip() {
+    let document =
+      "---\nauto_execution_mode: 3\ndescription: Check prompts\n---\n\n# Body\n---\nkept\n";
+    let (meta, body): (BTreeMap<String, serde_norway::Value>, String) = parse(document).unwrap();
+    assert_eq!(meta["description"], "Check prompts");
+    assert_eq!(body, "\n# Body\n---\nkept\n");
+    assert_eq!(render(&meta, &body).unwrap(), document);
+  }
+
+  #[test]
+  fn test_documents_without_front_matter() {
+    assert!(split("# Title\n").is_none());
+    assert_eq!(split("---\n---"), Some(("", "")));
+  }
+}
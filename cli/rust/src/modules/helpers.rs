@@ -0,0 +1,451 @@
+//! Template helpers for the agents core system
+//!
+//! This module provides the built-in handlebars helpers available to prompt authors
+//! and the declarative project helpers configured in `TemplateSettings::helpers`.
+//!
+//! | Helper | Usage | Result |
+//! |--------|-------|--------|
+//! | `indent` | `{{indent text 4}}` | Every line of `text` indented by 4 spaces (default 2) |
+//! | `bullet_list` | `{{bullet_list items}}` | One `- item` line per array element |
+//! | `code_block` | `{{code_block text "rust"}}` | `text` fenced as a code block with an optional language |
+//! | `join` | `{{join items ", "}}` | Array elements joined by the separator (default `, `) |
+//! | `include_file` | `{{include_file "docs/intro.md"}}` | File content, confined to the project root |
+//! | `date` | `{{date "%Y-%m-%d"}}` | Current UTC date (`%Y %m %d %H %M %S`) |
+//! | `platform` | `{{platform}}` | Detected platform name |
+//! | `env` | `{{env "CI"}}` | Environment variable, if allow-listed in the configuration |
+//! | `upper` / `lower` | `{{upper text}}` | Case-converted text |
+//!
+//! All helpers return values, so they can be nested as subexpressions, e.g.
+//! `{{indent (bullet_list items) 2}}`.
+
+use super::config::{HelperCase, HelperRule, HelperSettings};
+use super::error::{AgentError, Result};
+use super::platform::Platform;
+use handlebars::{
+  Context, Handlebars, Helper, HelperDef, RenderContext, RenderError, RenderErrorReason, ScopedJson,
+};
+use regex::Regex;
+use serde_json::Value;
+use std::path::{Path, PathBuf};
+use std::time::{SystemTime, UNIX_EPOCH};
+
+/// Names and descriptions of the built-in helpers
+pub const BUILTIN_HELPERS: &[(&str, &str)] = &[
+  (
+    "indent",
+    "Indent every line of a text by N spaces (default 2)",
+  ),
+  ("bullet_list", "Render an array as a Markdown bullet list"),
+  (
+    "code_block",
+    "Wrap a text in a fenced code block with an optional language",
+  ),
+  (
+    "join",
+    "Join array elements with a separator (default \", \")",
+  ),
+  (
+    "include_file",
+    "Include a file located inside the project root",
+  ),
+  ("date", "Current UTC date, formatted with %Y %m %d %H %M %S"),
+  ("platform", "Name of the detected platform"),
+  ("env", "Value of an allow-listed environment variable"),
+  ("upper", "Convert a text to upper case"),
+  ("lower", "Convert a text to lower case"),
+];
+
+/// Helpers registered by handlebars itself
+pub const HANDLEBARS_HELPERS: &[&str] = &[
+  "if", "unless", "each", "with", "lookup", "raw", "log", "eq", "ne", "gt", "gte", "lt", "lte",
+  "and", "or", "not", "len",
+];
+
+/// Check whether a name is a handlebars, built-in or project helper
+pub fn is_helper(name: &str, settings: &HelperSettings) -> bool {
+  HANDLEBARS_HELPERS.contains(&name)
+    || BUILTIN_HELPERS.iter().any(|(builtin, _)| *builtin == name)
+    || settings.rules.iter().any(|rule| rule.name == name)
+}
+
+type HelperFn = dyn Fn(&Helper<'_>) -> std::result::Result<Value, RenderError> + Send + Sync;
+
+/// Helper computing a value from its parameters
+struct ValueHelper(Box<HelperFn>);
+
+impl HelperDef for ValueHelper {
+  fn call_inner<'reg: 'rc, 'rc>(
+    &self,
+    h: &Helper<'rc>,
+    _: &'reg Handlebars<'reg>,
+    _: &'rc Context,
+    _: &mut RenderContext<'reg, 'rc>,
+  ) -> std::result::Result<ScopedJson<'rc>, RenderError> {
+    Ok(ScopedJson::Derived((self.0)(h)?))
+  }
+}
+
+fn register<F>(handlebars: &mut Handlebars<'static>, name: &str, f: F)
+where
+  F: Fn(&Helper<'_>) -> std::result::Result<Value, RenderError> + Send + Sync + 'static,
+{
+  handlebars.register_helper(name, Box::new(ValueHelper(Box::new(f))));
+}
+
+/// Register the built-in helpers
+///
+/// `include_file` resolves paths against `root` and refuses files outside of it;
+/// `env` only exposes the variables listed in `settings.env_allowlist`.
+pub fn register_builtin_helpers(
+  handlebars: &mut Handlebars<'static>,
+  root: &Path,
+  settings: &HelperSettings,
+) {
+  register(handlebars, "indent", |h| {
+    let text = text_param(h, 0)?;
+    let width = h.param(1).and_then(|p| p.value().as_u64()).unwrap_or(2) as usize;
+    Ok(Value::String(indent(&text, width)))
+  });
+
+  register(handlebars, "bullet_list", |h| {
+    let items = array_param(h, 0)?;
+    let lines: Vec<String> = items.iter().map(|i| format!("- {}", to_text(i))).collect();
+    Ok(Value::String(lines.join("\n")))
+  });
+
+  register(handlebars, "code_block", |h| {
+    let text = text_param(h, 0)?;
+    let language = h.param(1).map(|p| to_text(p.value())).unwrap_or_default();
+    Ok(Value::String(format!(
+      "```{}\n{}\n```",
+      language,
+      text.trim_end_matches('\n')
+    )))
+  });
+
+  register(handlebars, "join", |h| {
+    let items = array_param(h, 0)?;
+    let separator = h
+      .param(1)
+      .map(|p| to_text(p.value()))
+      .unwrap_or_else(|| ", ".to_string());
+    let parts: Vec<String> = items.iter().map(to_text).collect();
+    Ok(Value::String(parts.join(&separator)))
+  });
+
+  let root = root.to_path_buf();
+  register(handlebars, "include_file", move |h| {
+    let path = text_param(h, 0)?;
+    include_file(&root, &path).map(Value::String)
+  });
+
+  register(handlebars, "date", |h| {
+    let format = h
+      .param(0)
+      .map(|p| to_text(p.value()))
+      .unwrap_or_else(|| "%Y-%m-%d".to_string());
+    let now = SystemTime::now()
+      .duration_since(UNIX_EPOCH)
+      .map_err(|e| RenderErrorReason::Other(format!("System time error: {}", e)))?
+      .as_secs();
+    Ok(Value::String(format_date(now, &format)))
+  });
+
+  register(handlebars, "platform", |_| {
+    let platform = Platform::detect().map_err(|e| RenderErrorReason::Other(e.to_string()))?;
+    Ok(Value::String(platform.to_string()))
+  });
+
+  let allowlist = settings.env_allowlist.clone();
+  register(handlebars, "env", move |h| {
+    let name = text_param(h, 0)?;
+    if !allowlist.contains(&name) {
+      return Err(
+        RenderErrorReason::Other(format!(
+          "Environment variable '{}' is not in the helper allow-list",
+          name
+        ))
+        .into(),
+      );
+    }
+    Ok(Value::String(std::env::var(&name).unwrap_or_default()))
+  });
+
+  register(handlebars, "upper", |h| {
+    Ok(Value::String(text_param(h, 0)?.to_uppercase()))
+  });
+
+  register(handlebars, "lower", |h| {
+    Ok(Value::String(text_param(h, 0)?.to_lowercase()))
+  });
+}
+
+/// Register the declarative project helpers
+///
+/// Each rule transforms the helper's first parameter: regex replacements are applied
+/// in order, then the case conversion, then the prefix and suffix.
+pub fn register_rule_helpers(
+  handlebars: &mut Handlebars<'static>,
+  rules: &[HelperRule],
+) -> Result<()> {
+  for rule in rules {
+    if HANDLEBARS_HELPERS.contains(&rule.name.as_str())
+      || BUILTIN_HELPERS.iter().any(|(name, _)| *name == rule.name)
+    {
+      return Err(AgentError::Template(format!(
+        "Helper rule '{}' shadows a built-in helper",
+        rule.name
+      )));
+    }
+
+    let mut replacements = Vec::new();
+    for replacement in &rule.replace {
+      let pattern = Regex::new(&replacement.pattern).map_err(|e| {
+        AgentError::Template(format!(
+          "Invalid pattern in helper rule '{}': {}",
+          rule.name, e
+        ))
+      })?;
+      replacements.push((pattern, replacement.replacement.clone()));
+    }
+
+    let rule = rule.clone();
+    register(handlebars, &rule.name.clone(), move |h| {
+      let mut text = text_param(h, 0)?;
+      for (pattern, replacement) in &replacements {
+        text = pattern
+          .replace_all(&text, replacement.as_str())
+          .into_owned();
+      }
+      text = match rule.case {
+        Some(HelperCase::Upper) => text.to_uppercase(),
+        Some(HelperCase::Lower) => text.to_lowercase(),
+        None => text,
+      };
+      Ok(Value::String(format!(
+        "{}{}{}",
+        rule.prefix, text, rule.suffix
+      )))
+    });
+  }
+  Ok(())
+}
+
+/// Indent every non-empty line of a text
+pub fn indent(text: &str, width: usize) -> String {
+  let padding = " ".repeat(width);
+  text
+    .lines()
+    .map(|line| {
+      if line.is_empty() {
+        String::new()
+      } else {
+        format!("{}{}", padding, line)
+      }
+    })
+    .collect::<Vec<_>>()
+    .join("\n")
+}
+
+/// Read a file relative to `root`, refusing paths that escape it
+fn include_file(root: &Path, path: &str) -> std::result::Result<String, RenderError> {
+  let escape_error = || {
+    RenderError::from(RenderErrorReason::Other(format!(
+      "include_file: '{}' is outside of the project root",
+      path
+    )))
+  };
+  let root = root
+    .canonicalize()
+    .map_err(|e| RenderErrorReason::Other(format!("include_file: invalid root: {}", e)))?;
+  let resolved: PathBuf = root
+    .join(path)
+    .canonicalize()
+    .map_err(|e| RenderErrorReason::Other(format!("include_file: '{}': {}", path, e)))?;
+  if !resolved.starts_with(&root) {
+    return Err(escape_error());
+  }
+
+  std::fs::read_to_string(&resolved)
+    .map_err(|e| RenderErrorReason::Other(format!("include_file: '{}': {}", path, e)).into())
+}
+
+/// Format a UNIX timestamp (UTC) with `%Y %m %d %H %M %S` placeholders
+pub fn format_date(timestamp: u64, format: &str) -> String {
+  let days = (timestamp / 86_400) as i64;
+  let seconds = timestamp % 86_400;
+  let (year, month, day) = civil_from_days(days);
+
+  format
+    .replace("%Y", &format!("{:04}", year))
+    .replace("%m", &format!("{:02}", month))
+    .replace("%d", &format!("{:02}", day))
+    .replace("%H", &format!("{:02}", seconds / 3600))
+    .replace("%M", &format!("{:02}", seconds % 3600 / 60))
+    .replace("%S", &format!("{:02}", seconds % 60))
+}
+
+/// Convert days since the UNIX epoch to a (year, month, day) civil date
+fn civil_from_days(days: i64) -> (i64, u32, u32) {
+  let z = days + 719_468;
+  let era = z.div_euclid(146_097);
+  let doe = z.rem_euclid(146_097);
+  let yoe = (doe - doe / 1460 + doe / 36_524 - doe / 146_096) / 365;
+  let doy = doe - (365 * yoe + yoe / 4 - yoe / 100);
+  let mp = (5 * doy + 2) / 153;
+  let day = (doy - (153 * mp + 2) / 5 + 1) as u32;
+  let month = if mp < 10 { mp + 3 } else { mp - 9 } as u32;
+  let year = yoe + era * 400 + i64::from(month <= 2);
+  (year, month, day)
+}
+
+fn text_param(h: &Helper<'_>, index: usize) -> std::result::Result<String, RenderError> {
+  h.param(index)
+    .map(|p| to_text(p.value()))
+    .ok_or_else(|| missing_param_error(h, index))
+}
+
+fn array_param(h: &Helper<'_>, index: usize) -> std::result::Result<Vec<Value>, RenderError> {
+  match h.param(index).map(|p| p.value()) {
+    Some(Value::Array(items)) => Ok(items.clone()),
+    Some(Value::Null) => Ok(Vec::new()),
+    Some(other) => Ok(vec![other.clone()]),
+    None => Err(missing_param_error(h, index)),
+  }
+}
+
+fn missing_param_error(h: &Helper<'_>, index: usize) -> RenderError {
+  RenderErrorReason::Other(format!(
+    "Helper '{}' requires a parameter at index {}",
+    h.name(),
+    index
+  ))
+  .into()
+}
+
+/// Render a JSON value as plain text
+fn to_text(value: &Value) -> String {
+  match value {
+    Value::Null => String::new(),
+    Value::String(s) => s.clone(),
+    Value::Array(items) => items.iter().map(to_text).collect::<Vec<_>>().join("\n"),
+    other => other.to_string(),
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+  use crate::modules::config::HelperReplacement;
+
+  fn render(template: &str, data: Value, settings: &HelperSettings) -> Result<String> {
+    render_in(Path::new("."), template, data, settings)
+  }
+
+  fn render_in(
+    root: &Path,
+    template: &str,
+    data: Value,
+    settings: &HelperSettings,
+  ) -> Result<String> {
+    let mut handlebars = Handlebars::new();
+    handlebars.register_escape_fn(handlebars::no_escape);
+    register_builtin_helpers(&mut handlebars, root, settings);
+    register_rule_helpers(&mut handlebars, &settings.rules)?;
+    handlebars
+      .render_template(template, &data)
+      .map_err(|e| AgentError::Template(e.to_string()))
+  }
+
+  #[test]
+  fn test_builtin_helpers() {
+    let settings = HelperSettings::default();
+    let data = serde_json::json!({ "items": ["a", "b"], "text": "x\ny" });
+
+    assert_eq!(
+      render("{{indent (bullet_list items) 2}}", data.clone(), &settings).unwrap(),
+      "  - a\n  - b"
+    );
+    assert_eq!(
+      render("{{join items \" | \"}}", data.clone(), &settings).unwrap(),
+      "a | b"
+    );
+    assert_eq!(
+      render("{{code_block text \"sh\"}}", data.clone(), &settings).unwrap(),
+      "```sh\nx\ny\n```"
+    );
+    assert_eq!(
+      render("{{upper (lower \"MiXeD\")}}", data.clone(), &settings).unwrap(),
+      "MIXED"
+    );
+    assert!(render("{{env \"PATH\"}}", data, &settings).is_err());
+  }
+
+  #[test]
+  fn test_include_file_stays_in_root() {
+    let temp = tempfile::tempdir().unwrap();
+    let root = temp.path().join("project");
+    std::fs::create_dir_all(&root).unwrap();
+    std::fs::write(root.join("intro.md"), "Intro").unwrap();
+    std::fs::write(temp.path().join("secret.txt"), "secret").unwrap();
+    let settings = HelperSettings::default();
+
+    assert_eq!(
+      render_in(
+        &root,
+        "{{include_file \"intro.md\"}}",
+        Value::Null,
+        &settings
+      )
+      .unwrap(),
+      "Intro"
+    );
+    let error = render_in(
+      &root,
+      "{{include_file \"../secret.txt\"}}",
+      Value::Null,
+      &settings,
+    )
+    .unwrap_err();
+    assert!(error.to_string().contains("outside of the project root"));
+  }
+
+  #[test]
+  fn test_format_date() {
+    assert_eq!(format_date(0, "%Y-%m-%d"), "1970-01-01");
+    assert_eq!(
+      format_date(1_709_210_096, "%Y-%m-%d %H:%M:%S"),
+      "2024-02-29 12:34:56"
+    );
+  }
+
+  #[test]
+  fn test_rule_helpers() {
+    let settings = HelperSettings {
+      env_allowlist: Vec::new(),
+      rules: vec![HelperRule {
+        name: "slug".to_string(),
+        description: "Kebab-case slug".to_string(),
+        replace: vec![HelperReplacement {
+          pattern: r"\s+".to_string(),
+          replacement: "-".to_string(),
+        }],
+        case: Some(HelperCase::Lower),
+        prefix: "#".to_string(),
+        suffix: String::new(),
+      }],
+    };
+
+    assert_eq!(
+      render("{{slug \"Hello World\"}}", Value::Null, &settings).unwrap(),
+      "#hello-world"
+    );
+
+    for name in ["if", "each", "lookup", "upper"] {
+      let mut shadowing = settings.rules[0].clone();
+      shadowing.name = name.to_string();
+      let mut handlebars = Handlebars::new();
+      assert!(register_rule_helpers(&mut handlebars, &[shadowing]).is_err());
+    }
+  }
+}
@@ -0,0 +1,854 @@
+//! Template linting for the agents core system
+//!
+//! This module checks template files for problems that handlebars does not catch
+//! at registration time and reports them as diagnostics pointing into the files:
+//!
+//! | Code | Severity | Problem |
+//! |------|----------|---------|
+//! | `invalid-template` | error | File cannot be parsed or registered |
+//! | `duplicate-template` | error/warning | Name defined in several files (error for the same version) |
+//! | `undeclared-variable` | error | Variable used in the content but not declared |
+//! | `unused-variable` | warning | Declared variable never used by the template, its parent or partials |
+//! | `required-with-default` | warning | Required variable that also has a default |
+//! | `enum-on-non-string` | error | `values` on a variable that is neither a string nor an array |
+//! | `empty-section` | warning | Markdown section left empty when rendered with defaults |
+
+use super::config::{HelperSettings, PromptTemplate, TemplateVariable, VariableType};
+use super::error::{AgentError, Result};
+use super::frontmatter;
+use super::helpers;
+use super::template::TemplateRegistry;
+use regex::Regex;
+use serde::Serialize;
+use serde_json::Value;
+use std::collections::{HashMap, HashSet};
+use std::fmt;
+use std::path::{Path, PathBuf};
+use std::sync::LazyLock;
+
+/// Matches handlebars expressions, including triple-stash ones
+static MUSTACHE: LazyLock<Regex> =
+  LazyLock::new(|| Regex::new(r"(?s)\{\{\{?(.*?)\}?\}\}").expect("valid mustache regex"));
+
+/// Matches Markdown ATX headings
+static HEADING: LazyLock<Regex> =
+  LazyLock::new(|| Regex::new(r"^(#{1,6})\s+\S").expect("valid heading regex"));
+
+/// Block helpers whose body is rendered against a nested context
+const SCOPED_BLOCKS: &[&str] = &["each", "with"];
+
+/// Severity of a diagnostic
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Severity {
+  Error,
+  Warning,
+}
+
+impl fmt::Display for Severity {
+  fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+    match self {
+      Self::Error => write!(f, "error"),
+      Self::Warning => write!(f, "warning"),
+    }
+  }
+}
+
+/// Location of a diagnostic in a file, with 1-based lines and columns
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+pub struct Span {
+  pub line: usize,
+  pub column: usize,
+  pub end_line: usize,
+  pub end_column: usize,
+}
+
+impl Span {
+  /// Compute the span of the byte range `start..end` of a text
+  pub fn of(text: &str, start: usize, end: usize) -> Self {
+    let (line, column) = Self::position(text, start);
+    let (end_line, end_column) = Self::position(text, end);
+    Self {
+      line,
+      column,
+      end_line,
+      end_column,
+    }
+  }
+
+  fn position(text: &str, offset: usize) -> (usize, usize) {
+    let before = &text[..offset.min(text.len())];
+    let line = before.matches('\n').count() + 1;
+    let line_start = before.rfind('\n').map_or(0, |i| i + 1);
+    (line, before[line_start..].chars().count() + 1)
+  }
+}
+
+/// A problem found in a template file
+#[derive(Debug, Clone, Serialize)]
+pub struct Diagnostic {
+  pub severity: Severity,
+  pub code: &'static str,
+  pub message: String,
+  pub file: String,
+  #[serde(skip_serializing_if = "Option::is_none")]
+  pub template: Option<String>,
+  #[serde(skip_serializing_if = "Option::is_none")]
+  pub span: Option<Span>,
+}
+
+impl fmt::Display for Diagnostic {
+  fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+    write!(f, "{}", self.file)?;
+    if let Some(span) = &self.span {
+      write!(f, ":{}:{}", span.line, span.column)?;
+    }
+    write!(f, ": {}[{}]: {}", self.severity, self.code, self.message)
+  }
+}
+
+/// Result of linting template files
+#[derive(Debug, Clone, Default, Serialize)]
+pub struct LintReport {
+  pub diagnostics: Vec<Diagnostic>,
+}
+
+impl LintReport {
+  /// Count the diagnostics of a severity
+  pub fn count(&self, severity: Severity) -> usize {
+    self
+      .diagnostics
+      .iter()
+      .filter(|d| d.severity == severity)
+      .count()
+  }
+
+  /// Check whether any error was reported
+  pub fn has_errors(&self) -> bool {
+    self.count(Severity::Error) > 0
+  }
+
+  /// Serialize the report as JSON
+  pub fn to_json(&self) -> Result<String> {
+    Ok(serde_json::to_string_pretty(self)?)
+  }
+}
+
+impl fmt::Display for LintReport {
+  fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+    for diagnostic in &self.diagnostics {
+      writeln!(f, "{}", diagnostic)?;
+    }
+    write!(
+      f,
+      "{} error(s), {} warning(s)",
+      self.count(Severity::Error),
+      self.count(Severity::Warning)
+    )
+  }
+}
+
+/// A variable reference found in a template source
+#[derive(Debug, Clone, PartialEq, Eq)]
+struct Reference {
+  name: String,
+  /// Byte range of the whole expression in the source
+  start: usize,
+  end: usize,
+  /// Whether the reference is inside an `each`/`with` block, where it may
+  /// resolve against the nested context instead of the template variables
+  scoped: bool,
+}
+
+/// A template file with its raw text
+struct SourceFile {
+  path: PathBuf,
+  raw: String,
+  template: PromptTemplate,
+}
+
+impl SourceFile {
+  fn display(&self) -> String {
+    self.path.display().to_string()
+  }
+
+  /// Span of a byte range of the template content
+  fn content_span(&self, start: usize, end: usize) -> Option<Span> {
+    let content = &self.template.content;
+    if self.raw.ends_with(content.as_str()) && frontmatter::split(&self.raw).is_some() {
+      let offset = self.raw.len() - content.len();
+      return Some(Span::of(&self.raw, offset + start, offset + end));
+    }
+
+    // JSON escapes the content, so find the same occurrence of the expression
+    let needle = &content[start..end];
+    let occurrence = content[..start].matches(needle).count();
+    let anchor = self.raw.find("\"content\"").unwrap_or(0);
+    self.raw[anchor..]
+      .match_indices(needle)
+      .nth(occurrence)
+      .map(|(i, _)| Span::of(&self.raw, anchor + i, anchor + i + needle.len()))
+  }
+
+  /// Span of the first occurrence of a text anywhere in the file
+  fn text_span(&self, needle: &str) -> Option<Span> {
+    self
+      .raw
+      .find(needle)
+      .map(|i| Span::of(&self.raw, i, i + needle.len()))
+  }
+
+  /// Span of a `name: value` declaration in YAML or JSON
+  fn declaration_span(&self, value: &str) -> Option<Span> {
+    let pattern = format!(
+      r#""?name"?\s*:\s*['"]?{}['"]?\s*[,\r\n}}]"#,
+      regex::escape(value)
+    );
+    let found = Regex::new(&pattern).ok()?.find(&self.raw)?;
+    Some(Span::of(&self.raw, found.start(), found.end() - 1))
+  }
+}
+
+/// Linter checking template files
+pub struct TemplateLinter {
+  registry: TemplateRegistry,
+  helpers: HelperSettings,
+}
+
+impl TemplateLinter {
+  /// Create a linter resolving parents and partials from `registry`
+  pub fn new(registry: TemplateRegistry, helpers: HelperSettings) -> Self {
+    Self { registry, helpers }
+  }
+
+  /// Lint every template file of the given directories
+  pub fn lint_directories<P: AsRef<Path>>(&mut self, dirs: &[P]) -> Result<LintReport> {
+    let mut report = LintReport::default();
+    let mut files = Vec::new();
+
+    for dir in dirs {
+      let dir = dir.as_ref();
+      if !dir.exists() {
+        continue;
+      }
+      let mut paths = Vec::new();
+      for entry in std::fs::read_dir(dir)
+        .map_err(|e| AgentError::Storage(format!("Failed to read templates directory: {}", e)))?
+      {
+        let entry = entry
+          .map_err(|e| AgentError::Storage(format!("Failed to read directory entry: {}", e)))?;
+        let path = entry.path();
+        if path.is_file() && TemplateRegistry::is_template_file(&path) {
+          paths.push(path);
+        }
+      }
+      paths.sort();
+
+      for path in paths {
+        let raw = std::fs::read_to_string(&path)
+          .map_err(|e| AgentError::Storage(format!("Failed to read template file: {}", e)))?;
+        match self.registry.load_template_from_file(&path) {
+          Ok(template) => files.push(SourceFile {
+            path,
+            raw,
+            template,
+          }),
+          Err(error) => report.diagnostics.push(Diagnostic {
+            severity: Severity::Error,
+            code: "invalid-template",
+            message: error.to_string(),
+            file: path.display().to_string(),
+            template: None,
+            span: None,
+          }),
+        }
+      }
+    }
+
+    let duplicates = Self::check_duplicates(&files, &mut report);
+    self.register(&files, &duplicates, &mut report);
+    for file in &files {
+      let invalid = report
+        .diagnostics
+        .iter()
+        .any(|d| d.severity == Severity::Error && d.file == file.display());
+      report.diagnostics.extend(self.lint_file(file, invalid));
+    }
+
+    let position = |d: &Diagnostic| d.span.map(|s| (s.line, s.column));
+    report
+      .diagnostics
+      .sort_by(|a, b| (&a.file, position(a)).cmp(&(&b.file, position(b))));
+
+    Ok(report)
+  }
+
+  /// Report templates defined in several files, returning the indices to skip
+  fn check_duplicates(files: &[SourceFile], report: &mut LintReport) -> HashSet<usize> {
+    let mut seen: HashMap<&str, usize> = HashMap::new();
+    let mut skipped = HashSet::new();
+    for (index, file) in files.iter().enumerate() {
+      let name = file.template.name.as_str();
+      let Some(&first) = seen.get(name) else {
+        seen.insert(name, index);
+        continue;
+      };
+
+      let same_version = files[first].template.version == file.template.version;
+      if same_version {
+        skipped.insert(index);
+      }
+      report.diagnostics.push(Diagnostic {
+        severity: if same_version {
+          Severity::Error
+        } else {
+          Severity::Warning
+        },
+        code: "duplicate-template",
+        message: format!(
+          "Template '{}' {} is also defined in {}",
+          name,
+          file.template.version,
+          files[first].display()
+        ),
+        file: file.display(),
+        template: Some(file.template.name.clone()),
+        span: file.declaration_span(name),
+      });
+    }
+    skipped
+  }
+
+  /// Register the templates so children and renders can resolve them
+  fn register(&mut self, files: &[SourceFile], skipped: &HashSet<usize>, report: &mut LintReport) {
+    let mut pending: Vec<&SourceFile> = files
+      .iter()
+      .enumerate()
+      .filter(|(index, _)| !skipped.contains(index))
+      .map(|(_, file)| file)
+      .collect();
+
+    loop {
+      let (ready, waiting): (Vec<_>, Vec<_>) = pending.into_iter().partition(|file| {
+        file
+          .template
+          .extends
+          .as_ref()
+          .is_none_or(|parent| self.registry.get_template(parent).is_some())
+      });
+      if ready.is_empty() {
+        pending = waiting;
+        break;
+      }
+      for file in ready {
+        if let Err(error) = self.registry.register_template(file.template.clone()) {
+          report
+            .diagnostics
+            .push(Self::invalid(file, error.to_string()));
+        }
+      }
+      pending = waiting;
+    }
+
+    for file in pending {
+      let message = format!(
+        "Template '{}' extends unknown template '{}'",
+        file.template.name,
+        file.template.extends.as_deref().unwrap_or_default()
+      );
+      report.diagnostics.push(Self::invalid(file, message));
+    }
+  }
+
+  fn invalid(file: &SourceFile, message: String) -> Diagnostic {
+    Diagnostic {
+      severity: Severity::Error,
+      code: "invalid-template",
+      message,
+      file: file.display(),
+      template: Some(file.template.name.clone()),
+      span: file.declaration_span(&file.template.name),
+    }
+  }
+
+  /// Check the variables and rendered output of one template
+  fn lint_file(&self, file: &SourceFile, invalid: bool) -> Vec<Diagnostic> {
+    let template = &file.template;
+    let mut diagnostics = Vec::new();
+    let mut push = |severity, code, message: String, span| {
+      diagnostics.push(Diagnostic {
+        severity,
+        code,
+        message,
+        file: file.display(),
+        template: Some(template.name.clone()),
+        span,
+      })
+    };
+
+    let declared: HashSet<&str> = template.variables.iter().map(|v| v.name.as_str()).collect();
+
+    // Undeclared variables, reported once per name
+    let mut reported = HashSet::new();
+    let mut own = self.references(&template.content);
+    let mut block_references = Vec::new();
+    for body in template.blocks.values() {
+      block_references.extend(self.references(body));
+    }
+    for (reference, in_content) in own
+      .iter()
+      .map(|r| (r, true))
+      .chain(block_references.iter().map(|r| (r, false)))
+    {
+      if reference.scoped
+        || declared.contains(reference.name.as_str())
+        || !reported.insert(reference.name.clone())
+      {
+        continue;
+      }
+      let span = if in_content {
+        file.content_span(reference.start, reference.end)
+      } else {
+        None
+      };
+      push(
+        Severity::Error,
+        "undeclared-variable",
+        format!(
+          "Variable '{}' is used but not declared in `variables`",
+          reference.name
+        ),
+        span,
+      );
+    }
+
+    // Unused variables, counting the parent template and partials
+    own.extend(block_references);
+    let mut used: HashSet<String> = own.into_iter().map(|r| r.name).collect();
+    self.collect_indirect(template, &mut used, &mut HashSet::new());
+
+    for variable in &template.variables {
+      let span = file.declaration_span(&variable.name);
+      if !used.contains(&variable.name) {
+        push(
+          Severity::Warning,
+          "unused-variable",
+          format!("Variable '{}' is declared but never used", variable.name),
+          span,
+        );
+      }
+      if variable.required && variable.default.is_some() {
+        push(
+          Severity::Warning,
+          "required-with-default",
+          format!(
+            "Variable '{}' is required but has a default; make it optional",
+            variable.name
+          ),
+          span,
+        );
+      }
+      if variable.values.is_some()
+        && !matches!(variable.r#type, VariableType::String | VariableType::Array)
+      {
+        push(
+          Severity::Error,
+          "enum-on-non-string",
+          format!(
+            "Variable '{}' of type {} cannot restrict its `values`",
+            variable.name,
+            variable.r#type.name()
+          ),
+          span,
+        );
+      }
+    }
+
+    // Empty sections, only meaningful once the template renders
+    let has_errors = invalid
+      || diagnostics
+        .iter()
+        .any(|d: &Diagnostic| d.severity == Severity::Error);
+    if !has_errors {
+      let variables: HashMap<String, Value> = template
+        .variables
+        .iter()
+        .map(|v| (v.name.clone(), Self::sample_value(v)))
+        .collect();
+      match self.registry.render_source(template, &variables) {
+        Ok(rendered) => {
+          for heading in Self::empty_sections(&rendered) {
+            diagnostics.push(Diagnostic {
+              severity: Severity::Warning,
+              code: "empty-section",
+              message: format!("Section '{}' is empty when rendered with defaults", heading),
+              file: file.display(),
+              template: Some(template.name.clone()),
+              span: file.text_span(&heading),
+            });
+          }
+        }
+        Err(error) => diagnostics.push(Self::invalid(file, error.to_string())),
+      }
+    }
+
+    diagnostics
+  }
+
+  /// Collect the variables used by the parent template and referenced partials
+  fn collect_indirect(
+    &self,
+    template: &PromptTemplate,
+    used: &mut HashSet<String>,
+    visited: &mut HashSet<String>,
+  ) {
+    let mut sources = vec![template.content.clone()];
+    sources.extend(template.blocks.values().cloned());
+
+    while let Some(source) = sources.pop() {
+      for partial in Self::partials(&source) {
+        if !visited.insert(partial.clone()) {
+          continue;
+        }
+        if let Some(content) = self.registry.get_partial(&partial) {
+          used.extend(self.references(content).into_iter().map(|r| r.name));
+          sources.push(content.to_string());
+        }
+      }
+    }
+
+    if let Some(parent) = template
+      .extends
+      .as_ref()
+      .and_then(|parent| self.registry.get_template(parent))
+      && visited.insert(format!("{}@{}", parent.name, parent.version))
+    {
+      used.extend(self.references(&parent.content).into_iter().map(|r| r.name));
+      self.collect_indirect(parent, used, visited);
+    }
+  }
+
+  /// Names of the partials called from a source
+  fn partials(source: &str) -> Vec<String> {
+    MUSTACHE
+      .captures_iter(source)
+      .filter_map(|c| {
+        let inner = c.get(1)?.as_str().trim_matches('~').trim_start();
+        let rest = inner
+          .strip_prefix("#>")
+          .or_else(|| inner.strip_prefix('>'))?;
+        Some(rest.split_whitespace().next()?.to_string())
+      })
+      .collect()
+  }
+
+  /// Extract the variable references of a handlebars source
+  fn references(&self, source: &str) -> Vec<Reference> {
+    let mut references = Vec::new();
+    // Open blocks with whether their body renders against a nested context
+    let mut blocks: Vec<(String, bool)> = Vec::new();
+
+    for captures in MUSTACHE.captures_iter(source) {
+      let whole = captures.get(0).expect("whole match");
+      let inner = captures[1].trim_matches('~').trim();
+      let scoped = blocks.iter().any(|(_, scoped)| *scoped);
+      let (names, opened) = if inner.starts_with('!') {
+        continue;
+      } else if let Some(rest) = inner.strip_prefix("#*") {
+        (Vec::new(), Some((first_token(rest), false)))
+      } else if let Some(rest) = inner.strip_prefix("#>") {
+        let tokens = tokenize(rest);
+        (
+          self.paths(tokens.get(1..).unwrap_or_default(), false),
+          Some((first_token(rest), false)),
+        )
+      } else if let Some(rest) = inner.strip_prefix('>') {
+        let tokens = tokenize(rest);
+        (self.paths(tokens.get(1..).unwrap_or_default(), false), None)
+      } else if let Some(rest) = inner
+        .strip_prefix('#')
+        .or_else(|| inner.strip_prefix('^').filter(|r| !r.trim().is_empty()))
+      {
+        let tokens = tokenize(rest);
+        let name = first_token(rest);
+        if helpers::is_helper(&name, &self.helpers) {
+          let nested = SCOPED_BLOCKS.contains(&name.as_str());
+          (self.paths(&tokens[1..], false), Some((name, nested)))
+        } else {
+          // A section over a context path behaves like `with`
+          (self.paths(&tokens, false), Some((name, true)))
+        }
+      } else if let Some(rest) = inner.strip_prefix('/') {
+        let name = rest.trim();
+        if let Some(position) = blocks.iter().rposition(|(open, _)| open == name) {
+          blocks.truncate(position);
+        }
+        continue;
+      } else if inner == "^" || inner == "else" || inner.starts_with("else ") {
+        let tokens = tokenize(inner);
+        (self.paths(&tokens[1..], true), None)
+      } else {
+        (self.paths(&tokenize(inner), true), None)
+      };
+
+      references.extend(names.into_iter().map(|name| Reference {
+        name,
+        start: whole.start(),
+        end: whole.end(),
+        scoped,
+      }));
+      if let Some(block) = opened {
+        blocks.push(block);
+      }
+    }
+
+    references
+  }
+
+  /// Extract the root variable names of the paths in an expression
+  ///
+  /// With `callee` set, the first token is a helper call when it names a helper or
+  /// takes parameters; tokens following `(` always name a helper.
+  fn paths(&self, tokens: &[String], callee: bool) -> Vec<String> {
+    let mut names = Vec::new();
+    let mut expect_callee = callee;
+    let mut block_params = false;
+
+    for (index, token) in tokens.iter().enumerate() {
+      if token == "(" {
+        expect_callee = true;
+        continue;
+      }
+      if token == ")" {
+        continue;
+      }
+      if expect_callee {
+        expect_callee = false;
+        let has_params = tokens.get(index + 1).is_some_and(|next| next != ")");
+        if index > 0 || has_params || helpers::is_helper(token, &self.helpers) {
+          continue;
+        }
+      }
+      if token == "as" || token.starts_with('|') || block_params {
+        block_params = token.starts_with('|') && !token.ends_with('|') || token == "as";
+        continue;
+      }
+
+      let value = match token.split_once('=') {
+        Some((key, value)) if !key.starts_with(['"', '\'']) => value,
+        _ => token.as_str(),
+      };
+      if value.is_empty()
+        || value.starts_with(['"', '\'', '@', '.'])
+        || value.starts_with("this")
+        || value.parse::<f64>().is_ok()
+        || matches!(value, "true" | "false" | "null" | "undefined" | "else")
+      {
+        continue;
+      }
+      if let Some(root) = value.split(['.', '/', '[']).next()
+        && !root.is_empty()
+      {
+        names.push(root.to_string());
+      }
+    }
+
+    names
+  }
+
+  /// Value used for a variable when rendering with defaults
+  fn sample_value(variable: &TemplateVariable) -> Value {
+    if let Ok(Some(default)) = variable.default_value() {
+      return variable.coerce(&default).unwrap_or(default);
+    }
+    if !variable.required {
+      return Value::Null;
+    }
+    let sample = match variable.values.as_ref().and_then(|values| values.first()) {
+      Some(first) => first.clone(),
+      None => format!("<{}>", variable.name),
+    };
+    match variable.r#type {
+      VariableType::String => Value::String(sample),
+      VariableType::Number => Value::from(1),
+      VariableType::Boolean => Value::Bool(true),
+      VariableType::Array => Value::Array(vec![Value::String(sample)]),
+      VariableType::Object => Value::Object(Default::default()),
+    }
+  }
+
+  /// Find the headings of a Markdown document whose section has no content
+  fn empty_sections(markdown: &str) -> Vec<String> {
+    // Open sections as (level, heading, filled)
+    let mut open: Vec<(usize, String, bool)> = Vec::new();
+    let mut empty = Vec::new();
+    let mut in_fence = false;
+
+    for line in markdown.lines() {
+      if line.trim_start().starts_with("```") {
+        in_fence = !in_fence;
+      } else if !in_fence && let Some(captures) = HEADING.captures(line) {
+        let level = captures[1].len();
+        while let Some((outer, _, _)) = open.last()
+          && *outer >= level
+        {
+          let (_, heading, filled) = open.pop().expect("open section");
+          if !filled {
+            empty.push(heading);
+          }
+        }
+        // A nested heading gives content to every enclosing section
+        for (_, _, filled) in open.iter_mut() {
+          *filled = true;
+        }
+        open.push((level, line.trim().to_string(), false));
+        continue;
+      }
+
+      if !line.trim().is_empty() {
+        for (_, _, filled) in open.iter_mut() {
+          *filled = true;
+        }
+      }
+    }
+
+    empty.extend(
+      open
+        .into_iter()
+        .filter(|(_, _, filled)| !filled)
+        .map(|(_, heading, _)| heading),
+    );
+    empty
+  }
+}
+
+/// First token of an expression, with quotes removed
+fn first_token(expression: &str) -> String {
+  tokenize(expression)
+    .into_iter()
+    .next()
+    .unwrap_or_default()
+    .trim_matches(['"', '\''])
+    .to_string()
+}
+
+/// Split an expression into tokens, keeping quoted strings and block params whole
+fn tokenize(expression: &str) -> Vec<String> {
+  let mut tokens = Vec::new();
+  let mut current = String::new();
+  let mut quote = None;
+
+  for c in expression.chars() {
+    match (quote, c) {
+      (Some(q), _) => {
+        current.push(c);
+        if c == q {
+          quote = None;
+        }
+      }
+      (None, '"' | '\'') => {
+        current.push(c);
+        quote = Some(c);
+      }
+      (None, '(' | ')') => {
+        if !current.is_empty() {
+          tokens.push(std::mem::take(&mut current));
+        }
+        tokens.push(c.to_string());
+      }
+      (None, c) if c.is_whitespace() => {
+        if !current.is_empty() {
+          tokens.push(std::mem::take(&mut current));
+        }
+      }
+      (None, c) => current.push(c),
+    }
+  }
+  if !current.is_empty() {
+    tokens.push(current);
+  }
+  tokens
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  fn linter() -> TemplateLinter {
+    TemplateLinter::new(TemplateRegistry::new().unwrap(), HelperSettings::default())
+  }
+
+  fn codes(report: &LintReport) -> Vec<(&'static str, Option<usize>)> {
+    report
+      .diagnostics
+      .iter()
+      .map(|d| (d.code, d.span.map(|s| s.line)))
+      .collect()
+  }
+
+  #[test]
+  fn test_references_skip_helpers_literals_and_scoped_paths() {
+    let names: Vec<(String, bool)> = linter()
+      .references(
+        "{{title}} {{upper (join tags \", \")}} {{#if show}}{{../x}}{{/if}}\
+         {{#each items as |item|}}{{item.name}}{{/each}}{{> section-examples}}{{!-- {{no}} --}}",
+      )
+      .into_iter()
+      .map(|r| (r.name, r.scoped))
+      .collect();
+    assert_eq!(
+      names,
+      vec![
+        ("title".to_string(), false),
+        ("tags".to_string(), false),
+        ("show".to_string(), false),
+        ("items".to_string(), false),
+        ("item".to_string(), true),
+      ]
+    );
+  }
+
+  #[test]
+  fn test_lint_reports_spans_in_markdown_and_json() {
+    let temp = tempfile::tempdir().unwrap();
+    let (first, second) = (temp.path().join("a"), temp.path().join("b"));
+    std::fs::create_dir_all(&first).unwrap();
+    std::fs::create_dir_all(&second).unwrap();
+    std::fs::write(
+      first.join("notes.md"),
+      "---\nname: notes\ntype: memory\nvariables:\n  - name: title\n    type: string\n    \
+       required: true\n    default: Notes\n  - name: level\n    type: number\n    \
+       values: [\"1\"]\n---\n# {{title}}\n\n{{missing}}\n",
+    )
+    .unwrap();
+    std::fs::write(
+      second.join("notes.json"),
+      r##"{"name": "notes", "version": "2.0.0", "type": "memory", "description": "",
+  "metadata": {}, "variables": [], "content": "# Tips\n\n## Empty\n"}"##,
+    )
+    .unwrap();
+
+    let report = linter().lint_directories(&[first, second]).unwrap();
+    let markdown = report
+      .diagnostics
+      .iter()
+      .filter(|d| d.file.ends_with("notes.md"));
+    assert_eq!(
+      markdown
+        .map(|d| (d.code, d.span.map(|s| s.line)))
+        .collect::<Vec<_>>(),
+      vec![
+        ("required-with-default", Some(5)),
+        ("unused-variable", Some(9)),
+        ("enum-on-non-string", Some(9)),
+        ("undeclared-variable", Some(15)),
+      ]
+    );
+    assert!(codes(&report).contains(&("duplicate-template", Some(1))));
+    assert!(codes(&report).contains(&("empty-section", Some(2))));
+    assert!(report.has_errors());
+
+    let json: Value = serde_json::from_str(&report.to_json().unwrap()).unwrap();
+    assert_eq!(json["diagnostics"][0]["span"]["column"], 5);
+  }
+}
@@ -2,9 +2,19 @@
 //!
 //! This module contains the core functionality modules for the agents core system.
 
+pub mod cache;
+pub mod compile;
 pub mod config;
 pub mod error;
+pub mod frontmatter;
+pub mod helpers;
+pub mod lint;
 pub mod orchestrator;
 pub mod platform;
+pub mod remote;
+pub mod skill;
+pub mod snapshot;
 pub mod storage;
+pub mod sync;
 pub mod template;
+pub mod wizard;
@@ -3,12 +3,27 @@
 //! This module provides the main orchestration logic for managing agent operations
 //! like initialization, updates, composition, pruning, and synchronization.
 
+use super::cache::TemplateCache;
+use super::compile::{self, CompileManifest, CompileReport, Compiler, Translator};
 use super::config::{AgentConfig, PromptTemplate, TemplateType};
 use super::error::{AgentError, Result};
+use super::lint::{LintReport, TemplateLinter};
 use super::platform::Platform;
-use super::storage::{BackupInfo, StorageManager};
-use super::template::{TemplateContext, TemplateRegistry};
+use super::remote::RemoteTemplates;
+use super::skill;
+use super::snapshot::{self, SnapshotReport};
+use super::storage::{self, BackupInfo, StorageManager};
+use super::sync::{self, SyncManifest, SyncOutput, SyncRegistry};
+use super::template::{TemplateContext, TemplateRegistry, TemplateSource};
+use super::wizard::{self, ComposeWizard};
 use std::collections::HashMap;
+use std::path::{Path, PathBuf};
+
+/// File name of the compile manifest
+const COMPILE_MANIFEST_FILE: &str = "compile-manifest.json";
+
+/// File name of the sync manifest
+const SYNC_MANIFEST_FILE: &str = "sync-manifest.json";
 
 /// Main orchestrator for agent operations
 pub struct Orchestrator {
@@ -41,6 +56,18 @@ pub struct UpdateOptions {
 pub struct ComposeOptions {
   pub r#type: TemplateType,
   pub interactive: bool,
+  /// Template to compose from, defaults to the template of the type
+  pub template: Option<String>,
+  /// JSON or TOML file providing variable values
+  pub vars_file: Option<String>,
+  /// `key=value` overrides applied on top of the variables file
+  pub overrides: Vec<String>,
+  /// Output file, relative to the directory of the type
+  pub output: Option<String>,
+  /// Return the rendered prompt without writing it
+  pub stdout: bool,
+  /// Overwrite an existing output file
+  pub force: bool,
   pub silent: bool,
   pub verbose: bool,
 }
@@ -57,8 +84,12 @@ pub struct PruneOptions {
 /// Options for synchronization operations
 #[derive(Debug, Clone)]
 pub struct SyncOptions {
-  pub remote: Option<String>,
-  pub branch: Option<String>,
+  /// Project root the target files are written under
+  pub root: PathBuf,
+  /// Targets to sync, defaults to the targets enabled in the configuration
+  pub targets: Vec<String>,
+  /// Report the files that would change without writing them
+  pub dry_run: bool,
   pub silent: bool,
   pub verbose: bool,
 }
@@ -71,13 +102,17 @@ pub struct OperationResult {
   pub affected_files: Vec<String>,
   pub backup_info: Option<BackupInfo>,
   pub warnings: Vec<String>,
+  pub skipped: Vec<String>,
 }
 
 impl Orchestrator {
   /// Create a new orchestrator with the given configuration
   pub fn new(config: AgentConfig) -> Result<Self> {
-    let storage = StorageManager::new(&config.directories.memory)?;
-    let templates = TemplateRegistry::new()?;
+    let mut storage = StorageManager::new(&config.directories.memory)?;
+    // Skills keep their assets next to their entry, outside the memory directory
+    storage.include_directory(&config.directories.skill);
+    let mut templates = TemplateRegistry::new()?;
+    templates.configure_helpers(".", &config.templates.helpers)?;
     let platform = Platform::detect()?;
 
     Ok(Self {
@@ -100,6 +135,7 @@ impl Orchestrator {
       affected_files: Vec::new(),
       backup_info: None,
       warnings: Vec::new(),
+      skipped: Vec::new(),
     };
 
     // Check if repository already exists
@@ -127,6 +163,8 @@ impl Orchestrator {
       &self.config.directories.project,
       &self.config.directories.sub_agent,
       &self.config.directories.command,
+      &self.config.directories.skill,
+      &self.config.directories.rule,
       &self.config.directories.backups,
     ];
 
@@ -143,18 +181,12 @@ impl Orchestrator {
     }
 
     // Load default templates
-    let default_templates = TemplateRegistry::get_default_templates();
-    for template in default_templates {
-      self.templates.register_template(template.clone())?;
-
+    self.templates.register_builtin_templates()?;
+    for template in TemplateRegistry::get_default_templates() {
       // Save template to appropriate directory
-      let template_dir = match template.r#type {
-        TemplateType::Memory => &self.config.directories.memory,
-        TemplateType::SubAgent => &self.config.directories.sub_agent,
-        TemplateType::Command => &self.config.directories.command,
-      };
+      let template_dir = self.config.directories.directory_for(&template.r#type);
 
-      let template_file = format!("{}/{}.json", template_dir, template.name);
+      let template_file = format!("{}/{}.md", template_dir, template.name);
       self
         .templates
         .save_template_to_file(&template, &template_file)?;
@@ -193,6 +225,7 @@ impl Orchestrator {
       affected_files: Vec::new(),
       backup_info: None,
       warnings: Vec::new(),
+      skipped: Vec::new(),
     };
 
     // Check if repository exists
@@ -213,6 +246,22 @@ impl Orchestrator {
       }
     }
 
+    // Refresh remote template packs and compare them with the templates in use
+    self.templates.register_builtin_templates()?;
+    self.load_local_templates()?;
+    let loaded = self.load_remote_templates()?;
+    if !options.silent && loaded > 0 {
+      println!("Loaded {} remote templates", loaded);
+    }
+
+    for upgrade in self.templates.major_upgrades() {
+      let warning = format!("New major version: {}", upgrade);
+      if !options.silent {
+        println!("{}", warning);
+      }
+      result.warnings.push(warning);
+    }
+
     // TODO: Implement actual update logic
     // This would involve:
     // 1. Loading current configuration
@@ -230,6 +279,10 @@ impl Orchestrator {
       println!("Composing {:?} prompt...", options.r#type);
     }
 
+    // Register default and local templates
+    self.templates.register_builtin_templates()?;
+    self.load_templates()?;
+
     // Get templates of the specified type
     let templates = self
       .templates
@@ -242,25 +295,53 @@ impl Orchestrator {
     }
 
     if options.interactive {
-      // TODO: Implement interactive composition
-      // This would involve:
-      // 1. Presenting template options to user
-      // 2. Collecting variable values
-      // 3. Generating the prompt
-      return Err(AgentError::Internal(
-        "Interactive composition not yet implemented".to_string(),
-      ));
-    }
+      if !self.platform.is_terminal() {
+        return Err(AgentError::Internal(
+          "Interactive composition requires a terminal".to_string(),
+        ));
+      }
 
-    // Use first available template
-    let template = templates[0].clone();
+      let directory = self.config.directories.directory_for(&options.r#type);
+      let outcome = ComposeWizard::new(&self.templates, directory).run(&options.r#type)?;
+      let mut composed = outcome.template;
+      composed.metadata.insert(
+        "path".to_string(),
+        outcome.path.to_string_lossy().to_string(),
+      );
+      return Ok(composed);
+    }
 
-    // Create context with default values
-    let mut variables = HashMap::new();
-    for var in &template.variables {
-      if let Some(default) = &var.default {
-        variables.insert(var.name.clone(), serde_json::Value::String(default.clone()));
+    let template = match &options.template {
+      Some(name) => {
+        let template = self
+          .templates
+          .get_template(name)
+          .ok_or_else(|| AgentError::Template(format!("Template not found: {}", name)))?;
+        if template.r#type != options.r#type {
+          return Err(AgentError::Template(format!(
+            "Template '{}' is a {:?} template, not {:?}",
+            name, template.r#type, options.r#type
+          )));
+        }
+        template.clone()
       }
+      None => self
+        .templates
+        .default_template(&options.r#type)
+        .cloned()
+        .ok_or_else(|| {
+          AgentError::Template(format!("No templates found for type {:?}", options.r#type))
+        })?,
+    };
+
+    // Merge variable sources: defaults < variables file < overrides
+    let mut variables = match &options.vars_file {
+      Some(path) => TemplateRegistry::load_variables(path)?,
+      None => HashMap::new(),
+    };
+    for assignment in &options.overrides {
+      let (key, value) = TemplateRegistry::parse_assignment(assignment)?;
+      variables.insert(key, value);
     }
 
     let context = TemplateContext {
@@ -269,11 +350,54 @@ impl Orchestrator {
     };
 
     // Render template
-    let rendered = self.templates.render_template(&template.name, &context)?;
+    let rendered = self
+      .templates
+      .render_template(&format!("{}@{}", template.name, template.version), &context)?;
 
-    // Create new prompt template with rendered content
+    // Create new prompt template with rendered content, skills getting their
+    // entry front matter
     let mut new_template = template.clone();
-    new_template.content = rendered;
+    let skill_name = if options.r#type == TemplateType::Skill {
+      let (front_matter, document) = skill::compose_entry(&rendered, &context.variables)?;
+      new_template.content = document;
+      Some(front_matter.name)
+    } else {
+      new_template.content = rendered;
+      None
+    };
+
+    if options.stdout {
+      return Ok(new_template);
+    }
+
+    let directory = Path::new(self.config.directories.directory_for(&options.r#type));
+    let output = options.output.as_ref().map(|output| {
+      Path::new(output)
+        .strip_prefix(directory)
+        .map(|p| p.to_string_lossy().to_string())
+        .unwrap_or_else(|_| output.clone())
+    });
+    let file_name = match (skill_name, output) {
+      // The output of a skill names its directory
+      (Some(name), output) => skill::entry_file_name(output.as_deref().unwrap_or(&name)),
+      (None, Some(output)) => output,
+      (None, None) => wizard::default_file_name(&template, &context),
+    };
+    let path = wizard::resolve_target(directory, &file_name)?;
+    if path.exists() && !options.force {
+      return Err(AgentError::Storage(format!(
+        "{} already exists. Use --force to overwrite.",
+        path.display()
+      )));
+    }
+
+    storage::write_atomic(&path, new_template.content.as_bytes())?;
+    if !options.silent {
+      println!("Created prompt: {}", path.display());
+    }
+    new_template
+      .metadata
+      .insert("path".to_string(), path.to_string_lossy().to_string());
 
     Ok(new_template)
   }
@@ -290,6 +414,7 @@ impl Orchestrator {
       affected_files: Vec::new(),
       backup_info: None,
       warnings: Vec::new(),
+      skipped: Vec::new(),
     };
 
     // TODO: Implement pruning logic
@@ -302,31 +427,240 @@ impl Orchestrator {
     Ok(result)
   }
 
-  /// Sync with remote repository
+  /// Sync the canonical prompts to the files of the enabled tool targets
   pub async fn sync(&mut self, options: SyncOptions) -> Result<OperationResult> {
-    if !options.silent {
-      println!("Syncing with remote repository...");
-    }
-
     let mut result = OperationResult {
       success: false,
       message: "Sync completed".to_string(),
       affected_files: Vec::new(),
       backup_info: None,
       warnings: Vec::new(),
+      skipped: Vec::new(),
     };
 
-    // TODO: Implement sync logic
-    // This would involve:
-    // 1. Connecting to remote repository
-    // 2. Pulling updates
-    // 3. Pushing local changes
-    // 4. Handling conflicts
+    let registry = SyncRegistry::new();
+    let targets = if options.targets.is_empty() {
+      registry.enabled(&self.config.sync)?
+    } else {
+      registry.resolve(&options.targets)?
+    };
+    if targets.is_empty() {
+      result.message = "No sync targets enabled".to_string();
+      result.success = true;
+      return Ok(result);
+    }
 
-    result.success = true;
+    let prompts = sync::collect_prompts(&self.config.directories, &mut result.warnings)?;
+    let manifest_path = self.state_path(SYNC_MANIFEST_FILE);
+    let mut manifest = SyncManifest::load(&manifest_path)?;
+    let mut failed = Vec::new();
+    for target in targets {
+      if !options.silent {
+        println!("Syncing {}...", target.id());
+      }
+      let mut output = SyncOutput::new(&options.root)
+        .with_settings(self.config.sync.settings_for(target.id()))
+        .with_manifest(&manifest, target.id());
+      // A failing target is reported without undoing the targets already written
+      let changes = output
+        .select(&prompts)
+        .and_then(|selected| target.render(&selected, &mut output))
+        .and_then(|()| sync::apply(target, &mut output, &mut manifest, options.dry_run));
+      result.warnings.extend(
+        output
+          .warnings()
+          .iter()
+          .map(|warning| format!("{}: {}", target.id(), warning)),
+      );
+      result.skipped.extend(
+        output
+          .skipped()
+          .iter()
+          .map(|(prompt, reason)| format!("{}: skipped {}, {}", target.id(), prompt, reason)),
+      );
+      let changes = match changes {
+        Ok(changes) => changes,
+        Err(error) => {
+          result
+            .warnings
+            .push(format!("{}: failed, {}", target.id(), error));
+          failed.push(target.id());
+          continue;
+        }
+      };
+      for path in &changes.written {
+        if options.verbose && !options.silent {
+          println!("  {}", path.display());
+        }
+      }
+      for path in &changes.removed {
+        if options.verbose && !options.silent {
+          println!("  removed {}", path.display());
+        }
+      }
+      result.affected_files.extend(
+        changes
+          .written
+          .iter()
+          .chain(&changes.removed)
+          .map(|path| path.to_string_lossy().to_string()),
+      );
+    }
+    if !options.dry_run {
+      manifest.save(&manifest_path)?;
+    }
+
+    if !options.silent {
+      for line in result.skipped.iter().chain(&result.warnings) {
+        println!("{}", line);
+      }
+    }
+    result.message = format!(
+      "{} {} file(s)",
+      if options.dry_run {
+        "Would write"
+      } else {
+        "Wrote"
+      },
+      result.affected_files.len()
+    );
+    if !failed.is_empty() {
+      result.message = format!("{}, {} failed", result.message, failed.join(", "));
+    }
+    result.success = failed.is_empty();
     Ok(result)
   }
 
+  /// Load the local templates and the templates of the remote packs
+  pub fn load_templates(&mut self) -> Result<usize> {
+    Ok(self.load_local_templates()? + self.load_remote_templates()?)
+  }
+
+  /// Load partials and templates from the project and user templates directories
+  ///
+  /// User templates override project templates of the same name.
+  pub fn load_local_templates(&mut self) -> Result<usize> {
+    let local_dir = std::path::Path::new(&self.config.templates.local_directory);
+    self
+      .templates
+      .load_partials_from_directory(local_dir.join("partials"))?;
+    let mut loaded = self.templates.load_templates_from_directory(local_dir)?;
+
+    let user_dir = match &self.config.templates.user_directory {
+      Some(dir) => PathBuf::from(dir),
+      None => self.platform.config_dir()?.join("templates"),
+    };
+    loaded += self
+      .templates
+      .load_namespaced_templates(user_dir, None, TemplateSource::User)?;
+    Ok(loaded)
+  }
+
+  /// Lint template directories, defaulting to the local templates directory
+  pub fn lint_templates(&self, dirs: &[PathBuf]) -> Result<LintReport> {
+    let local_dir = Path::new(&self.config.templates.local_directory);
+    let dirs = if dirs.is_empty() {
+      vec![local_dir.to_path_buf()]
+    } else {
+      dirs.to_vec()
+    };
+
+    let mut registry = TemplateRegistry::new()?;
+    registry.configure_helpers(".", &self.config.templates.helpers)?;
+    registry.register_builtin_templates()?;
+    registry.load_partials_from_directory(local_dir.join("partials"))?;
+    let packs = self.config.templates.remote_packs();
+    if !packs.is_empty() {
+      RemoteTemplates::new(&self.config.templates.cache)?.load_into(&mut registry, &packs)?;
+    }
+    TemplateLinter::new(registry, self.config.templates.helpers.clone()).lint_directories(&dirs)
+  }
+
+  /// Run the template snapshots of directories, defaulting to the local templates
+  /// directory; with `update`, mismatching snapshots are rewritten
+  pub fn test_templates(&mut self, dirs: &[PathBuf], update: bool) -> Result<SnapshotReport> {
+    self.templates.register_builtin_templates()?;
+    self.load_templates()?;
+
+    let local_dir = Path::new(&self.config.templates.local_directory);
+    let dirs = if dirs.is_empty() {
+      vec![local_dir.to_path_buf()]
+    } else {
+      dirs.to_vec()
+    };
+
+    let mut report = SnapshotReport::default();
+    for dir in &dirs {
+      if dir != local_dir {
+        self.templates.load_templates_from_directory(dir)?;
+      }
+      report
+        .results
+        .extend(snapshot::run_snapshots(&self.templates, dir, update)?.results);
+    }
+    Ok(report)
+  }
+
+  /// Compile the `.src.md` prompt sources under paths, defaulting to the
+  /// directories of every prompt type
+  pub fn compile_prompts(
+    &mut self,
+    paths: &[PathBuf],
+    translator: &dyn Translator,
+    force: bool,
+  ) -> Result<CompileReport> {
+    // Sources render with the partials of the built-in and local templates
+    self.templates.register_builtin_templates()?;
+    self.load_templates()?;
+
+    let sources = if paths.is_empty() {
+      let directories = &self.config.directories;
+      compile::discover(&[
+        &directories.user,
+        &directories.project,
+        &directories.memory,
+        &directories.sub_agent,
+        &directories.command,
+        &directories.skill,
+        &directories.rule,
+      ])
+    } else {
+      compile::discover(paths)
+    };
+
+    let manifest_path = self.state_path(COMPILE_MANIFEST_FILE);
+    let mut manifest = CompileManifest::load(&manifest_path)?;
+    let cache = TemplateCache::open(&self.config.templates.cache)?;
+    let report = Compiler::new(&self.templates, translator)
+      .with_cache(cache)
+      .compile(&sources, &mut manifest, force);
+    manifest.save(&manifest_path)?;
+    Ok(report)
+  }
+
+  /// Path of a state file, next to the backups directory
+  fn state_path(&self, file: &str) -> PathBuf {
+    Path::new(&self.config.directories.backups)
+      .parent()
+      .unwrap_or(Path::new(""))
+      .join(file)
+  }
+
+  /// Load and validate the skills of the skills directory
+  pub fn load_skills(&self) -> Result<Vec<skill::Skill>> {
+    skill::discover(&self.config.directories.skill)
+  }
+
+  /// Fetch the configured remote template packs and register their templates
+  pub fn load_remote_templates(&mut self) -> Result<usize> {
+    let packs = self.config.templates.remote_packs();
+    if packs.is_empty() {
+      return Ok(0);
+    }
+
+    RemoteTemplates::new(&self.config.templates.cache)?.load_into(&mut self.templates, &packs)
+  }
+
   /// Get the current configuration
   pub fn config(&self) -> &AgentConfig {
     &self.config
@@ -350,8 +684,12 @@ impl Orchestrator {
   /// Load configuration from storage
   pub fn load_config(&mut self) -> Result<()> {
     let config_content = self.storage.read_file_to_string("agents.prompts.json")?;
-    self.config = serde_json::from_str(&config_content)
+    let config: AgentConfig = serde_json::from_str(&config_content)
       .map_err(|e| AgentError::Configuration(format!("Failed to parse configuration: {}", e)))?;
+    for pack in config.templates.remote_packs() {
+      pack.validate()?;
+    }
+    self.config = config;
     Ok(())
   }
 
@@ -367,3 +705,198 @@ impl Orchestrator {
     Ok(())
   }
 }
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  fn config_in(root: &Path) -> AgentConfig {
+    let mut config = AgentConfig::default();
+    let dir = |name: &str| root.join(name).to_string_lossy().to_string();
+    config.directories.memory = dir("__ai/locale");
+    config.directories.command = dir("__ai/cmd");
+    config.directories.sub_agent = dir("__ai/sa");
+    config.directories.skill = dir("__ai/skills");
+    config.directories.rule = dir("__ai/rules");
+    config.directories.backups = dir(".agents/backups");
+    config.templates.local_directory = dir("__ai/templates");
+    config.templates.user_directory = Some(dir("user-templates"));
+    config
+  }
+
+  fn compose_options(r#type: TemplateType) -> ComposeOptions {
+    ComposeOptions {
+      r#type,
+      interactive: false,
+      template: None,
+      vars_file: None,
+      overrides: Vec::new(),
+      output: None,
+      stdout: false,
+      force: false,
+      silent: true,
+      verbose: false,
+    }
+  }
+
+  #[tokio::test]
+  async fn test_compose_merges_variable_sources_and_writes_output() {
+    let temp = tempfile::tempdir().unwrap();
+    let vars = temp.path().join("vars.json");
+    std::fs::write(
+      &vars,
+      r#"{"command_name": "review", "description": "Review code", "usage": "from file"}"#,
+    )
+    .unwrap();
+
+    let mut orchestrator = Orchestrator::new(config_in(temp.path())).unwrap();
+    let composed = orchestrator
+      .compose(ComposeOptions {
+        template: Some("command-default".to_string()),
+        vars_file: Some(vars.to_string_lossy().to_string()),
+        overrides: vec!["usage=/review <path>".to_string()],
+        output: Some("review".to_string()),
+        ..compose_options(TemplateType::Command)
+      })
+      .await
+      .unwrap();
+
+    let path = temp.path().join("__ai/cmd/review.md");
+    assert_eq!(
+      composed.metadata.get("path").map(Path::new),
+      Some(path.as_path())
+    );
+    let written = std::fs::read_to_string(&path).unwrap();
+    assert!(written.starts_with("# review\n"));
+    assert!(written.contains("/review <path>"));
+
+    // Existing files are only replaced with force
+    let again = orchestrator
+      .compose(ComposeOptions {
+        vars_file: Some(vars.to_string_lossy().to_string()),
+        output: Some("review.md".to_string()),
+        ..compose_options(TemplateType::Command)
+      })
+      .await;
+    assert!(again.is_err());
+  }
+
+  #[tokio::test]
+  async fn test_compose_skill_writes_entry_directory() {
+    let temp = tempfile::tempdir().unwrap();
+    let mut orchestrator = Orchestrator::new(config_in(temp.path())).unwrap();
+    let overrides = |name: &str| {
+      vec![
+        format!("name={}", name),
+        "description=Format timestamps".to_string(),
+        "when_to_use=Dates in logs".to_string(),
+        "instructions=Use ISO 8601".to_string(),
+      ]
+    };
+
+    orchestrator
+      .compose(ComposeOptions {
+        overrides: overrides("timestamp"),
+        ..compose_options(TemplateType::Skill)
+      })
+      .await
+      .unwrap();
+    let written =
+      std::fs::read_to_string(temp.path().join("__ai/skills/timestamp/SKILL.md")).unwrap();
+    assert!(written.starts_with("---\nname: timestamp\ndescription: Format timestamps\n---\n"));
+
+    let invalid = orchestrator
+      .compose(ComposeOptions {
+        overrides: overrides("Time Stamp"),
+        ..compose_options(TemplateType::Skill)
+      })
+      .await;
+    assert!(invalid.is_err());
+  }
+
+  #[tokio::test]
+  async fn test_sync_reports_target_warnings() {
+    let temp = tempfile::tempdir().unwrap();
+    let rules = temp.path().join("__ai/rules");
+    std::fs::create_dir_all(&rules).unwrap();
+    std::fs::write(
+      rules.join("review.md"),
+      "---\nscope: model-decision\n---\n\nReview.\n",
+    )
+    .unwrap();
+
+    let mut orchestrator = Orchestrator::new(config_in(temp.path())).unwrap();
+    let options = SyncOptions {
+      root: temp.path().to_path_buf(),
+      targets: vec!["kiro".to_string()],
+      dry_run: false,
+      silent: true,
+      verbose: false,
+    };
+    let result = orchestrator.sync(options.clone()).await.unwrap();
+    assert_eq!(result.affected_files.len(), 1);
+    assert!(result.warnings[0].starts_with("kiro: review.md has the model-decision scope"));
+    assert!(temp.path().join(".kiro/steering/review.md").exists());
+
+    let again = orchestrator.sync(options).await.unwrap();
+    assert!(again.affected_files.is_empty());
+  }
+
+  #[tokio::test]
+  async fn test_sync_failure_keeps_other_targets() {
+    let temp = tempfile::tempdir().unwrap();
+    let rules = temp.path().join("__ai/rules");
+    let commands = temp.path().join("__ai/cmd");
+    std::fs::create_dir_all(&rules).unwrap();
+    std::fs::create_dir_all(&commands).unwrap();
+    std::fs::write(rules.join("style.md"), "Style.\n").unwrap();
+    std::fs::write(commands.join("build.md"), "Build the project.\n").unwrap();
+
+    let mut config = config_in(temp.path());
+    config.sync.settings.insert(
+      "windsurf".to_string(),
+      crate::modules::config::TargetSettings {
+        max_size: Some(10),
+        ..Default::default()
+      },
+    );
+    let mut orchestrator = Orchestrator::new(config).unwrap();
+    let options = SyncOptions {
+      root: temp.path().to_path_buf(),
+      targets: vec!["kiro".to_string(), "windsurf".to_string()],
+      dry_run: false,
+      silent: true,
+      verbose: false,
+    };
+    let result = orchestrator.sync(options.clone()).await.unwrap();
+    assert!(!result.success);
+    assert!(result.message.ends_with("windsurf failed"));
+    assert!(result.warnings[0].starts_with("windsurf: failed, "));
+    assert!(temp.path().join(".kiro/steering/style.md").exists());
+
+    // The manifest records the targets that succeeded
+    let again = orchestrator
+      .sync(SyncOptions {
+        targets: vec!["kiro".to_string()],
+        ..options
+      })
+      .await
+      .unwrap();
+    assert!(again.success);
+    assert!(again.affected_files.is_empty());
+  }
+
+  #[tokio::test]
+  async fn test_compose_rejects_template_of_other_type() {
+    let temp = tempfile::tempdir().unwrap();
+    let mut orchestrator = Orchestrator::new(config_in(temp.path())).unwrap();
+    let result = orchestrator
+      .compose(ComposeOptions {
+        template: Some("memory-default".to_string()),
+        stdout: true,
+        ..compose_options(TemplateType::Command)
+      })
+      .await;
+    assert!(result.is_err());
+  }
+}
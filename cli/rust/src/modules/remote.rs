@@ -0,0 +1,316 @@
+//! Remote template repositories for the agents core system
+//!
+//! This module fetches template packs from git repositories at a pinned reference,
+//! keeps the checkouts in the template cache and loads them into the template
+//! registry under their namespace.
+
+use super::cache::{CacheLookup, TemplateCache};
+use super::config::{CacheSettings, TemplatePack};
+use super::error::{AgentError, Result};
+use super::template::{TemplateRegistry, TemplateSource};
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::process::Command;
+
+/// Fetcher for remote template packs
+pub struct RemoteTemplates {
+  cache: TemplateCache,
+}
+
+impl RemoteTemplates {
+  /// Create a new fetcher storing checkouts in the configured cache
+  pub fn new(cache: &CacheSettings) -> Result<Self> {
+    Ok(Self {
+      cache: TemplateCache::open(cache)?,
+    })
+  }
+
+  /// Get the underlying cache
+  pub fn cache(&self) -> &TemplateCache {
+    &self.cache
+  }
+
+  /// Get the checkout directory of a pack
+  pub fn checkout_dir(&self, pack: &TemplatePack) -> PathBuf {
+    self.cache.path_for(&Self::cache_key(pack))
+  }
+
+  /// Fetch a pack, reusing the cached checkout while it is fresh or its source is unchanged
+  pub fn fetch(&mut self, pack: &TemplatePack) -> Result<PathBuf> {
+    pack.validate()?;
+    let key = Self::cache_key(pack);
+    let checkout = self.cache.path_for(&key);
+    let reference = pack.reference.as_deref().unwrap_or("HEAD");
+
+    let stale = match self.cache.lookup(&key, None)? {
+      CacheLookup::Fresh(_) => {
+        tracing::debug!("Using cached template pack {}", pack.url);
+        self.cache.touch(&key)?;
+        return Ok(checkout);
+      }
+      CacheLookup::Stale(entry) => Some(entry),
+      CacheLookup::Miss => None,
+    };
+
+    // Revalidate against the remote before fetching again
+    match Self::remote_commit(&pack.url, reference) {
+      Ok(Some(commit)) if stale.as_ref().is_some_and(|e| e.source_hash == commit) => {
+        self.cache.revalidate(&key)?;
+        return Ok(checkout);
+      }
+      Ok(_) => {}
+      Err(error) => {
+        if stale.is_some() && self.cache.is_offline() {
+          tracing::warn!("Serving stale template pack {}: {}", pack.url, error);
+          self.cache.touch(&key)?;
+          return Ok(checkout);
+        }
+        return Err(error);
+      }
+    }
+
+    if checkout.join(".git").exists() {
+      Self::git(
+        Some(&checkout),
+        &["fetch", "--quiet", "--tags", "--force", "origin"],
+      )?;
+    } else {
+      if checkout.exists() {
+        fs::remove_dir_all(&checkout)
+          .map_err(|e| AgentError::Storage(format!("Failed to clear pack checkout: {}", e)))?;
+      }
+      if let Some(parent) = checkout.parent() {
+        fs::create_dir_all(parent)
+          .map_err(|e| AgentError::Storage(format!("Failed to create cache directory: {}", e)))?;
+      }
+      let target = checkout.to_string_lossy().to_string();
+      Self::git(
+        None,
+        &[
+          "clone",
+          "--quiet",
+          "--no-checkout",
+          "--",
+          &pack.url,
+          &target,
+        ],
+      )?;
+    }
+
+    let commit = Self::resolve_reference(&checkout, reference)?;
+    Self::git(
+      Some(&checkout),
+      &["checkout", "--quiet", "--force", "--detach", &commit],
+    )?;
+
+    self.cache.insert(&key, &commit)?;
+    Ok(checkout)
+  }
+
+  /// Fetch every pack and register its templates under the pack namespace
+  pub fn load_into(
+    &mut self,
+    registry: &mut TemplateRegistry,
+    packs: &[TemplatePack],
+  ) -> Result<usize> {
+    let mut loaded_count = 0;
+    for pack in packs {
+      let checkout = self.fetch(pack)?;
+      let dir = match &pack.path {
+        Some(path) => checkout.join(path),
+        None => checkout,
+      };
+      loaded_count +=
+        registry.load_namespaced_templates(&dir, Some(&pack.namespace), TemplateSource::Remote)?;
+    }
+    Ok(loaded_count)
+  }
+
+  /// Compute the cache key of a pack from its URL and reference
+  fn cache_key(pack: &TemplatePack) -> String {
+    format!(
+      "pack:{}@{}",
+      pack.url,
+      pack.reference.as_deref().unwrap_or("HEAD")
+    )
+  }
+
+  /// Ask the remote which commit a reference points to
+  ///
+  /// Returns `Ok(None)` when the reference is not advertised by the remote, which is
+  /// the case for pinned commit ids, and an error when the remote is unreachable.
+  fn remote_commit(url: &str, reference: &str) -> Result<Option<String>> {
+    // An annotated tag is advertised as the tag object, peeled to its commit as `<tag>^{}`
+    let peeled = format!("{}^{{}}", reference);
+    let output = Self::git(None, &["ls-remote", "--", url, reference, &peeled])?;
+    let refs: Vec<(&str, &str)> = output
+      .lines()
+      .filter_map(|line| line.split_once(char::is_whitespace))
+      .collect();
+    Ok(
+      refs
+        .iter()
+        .find(|(_, name)| name.trim().ends_with("^{}"))
+        .or_else(|| refs.first())
+        .map(|(commit, _)| commit.to_string()),
+    )
+  }
+
+  /// Resolve a branch, tag or commit to a commit id
+  fn resolve_reference(checkout: &Path, reference: &str) -> Result<String> {
+    let candidates = [
+      format!("origin/{}^{{commit}}", reference),
+      format!("{}^{{commit}}", reference),
+    ];
+    for candidate in &candidates {
+      if let Ok(commit) = Self::git(
+        Some(checkout),
+        &["rev-parse", "--quiet", "--verify", candidate],
+      ) {
+        return Ok(commit);
+      }
+    }
+    Err(AgentError::Remote(format!(
+      "Reference '{}' not found in {}",
+      reference,
+      checkout.display()
+    )))
+  }
+
+  /// Run a git command and return its trimmed standard output
+  fn git(dir: Option<&Path>, args: &[&str]) -> Result<String> {
+    let mut command = Command::new("git");
+    if let Some(dir) = dir {
+      command.arg("-C").arg(dir);
+    }
+    let output = command
+      .args(args)
+      .output()
+      .map_err(|e| AgentError::Remote(format!("Failed to run git: {}", e)))?;
+
+    if !output.status.success() {
+      return Err(AgentError::Remote(format!(
+        "git {} failed: {}",
+        args.join(" "),
+        String::from_utf8_lossy(&output.stderr).trim()
+      )));
+    }
+    Ok(String::from_utf8_lossy(&output.stdout).trim().to_string())
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+  use crate::modules::config::PromptTemplate;
+
+  fn git(dir: &Path, args: &[&str]) {
+    let status = Command::new("git")
+      .arg("-C")
+      .arg(dir)
+      .args(["-c", "user.name=test", "-c", "user.email=test@example.com"])
+      .args(args)
+      .output()
+      .unwrap()
+      .status;
+    assert!(status.success(), "git {:?} failed", args);
+  }
+
+  fn write_template(dir: &Path, name: &str, content: &str) {
+    let template = PromptTemplate {
+      name: name.to_string(),
+      content: content.to_string(),
+      ..PromptTemplate::default()
+    };
+    fs::write(
+      dir.join(format!("{}.json", name)),
+      serde_json::to_string_pretty(&template).unwrap(),
+    )
+    .unwrap();
+  }
+
+  #[test]
+  fn test_fetch_pinned_reference_into_namespace() {
+    let temp = tempfile::tempdir().unwrap();
+    let source = temp.path().join("source");
+    fs::create_dir_all(&source).unwrap();
+    git(&source, &["init", "--quiet"]);
+    write_template(&source, "greeting", "Hello v1");
+    git(&source, &["add", "."]);
+    git(&source, &["commit", "--quiet", "-m", "v1"]);
+    git(&source, &["tag", "v1"]);
+    write_template(&source, "greeting", "Hello v2");
+    git(&source, &["commit", "--quiet", "-am", "v2"]);
+
+    let cache = CacheSettings {
+      directory: temp.path().join("cache").to_string_lossy().to_string(),
+      ..CacheSettings::default()
+    };
+    let pack = TemplatePack {
+      url: format!("file://{}", source.display()),
+      reference: Some("v1".to_string()),
+      namespace: "team".to_string(),
+      path: None,
+    };
+
+    let mut registry = TemplateRegistry::new().unwrap();
+    let loaded = RemoteTemplates::new(&cache)
+      .unwrap()
+      .load_into(&mut registry, std::slice::from_ref(&pack))
+      .unwrap();
+
+    assert_eq!(loaded, 1);
+    let template = registry.get_template("team/greeting").unwrap();
+    assert_eq!(template.content, "Hello v1");
+  }
+
+  #[test]
+  fn test_annotated_tag_resolves_to_its_commit() {
+    let temp = tempfile::tempdir().unwrap();
+    let source = temp.path().join("source");
+    fs::create_dir_all(&source).unwrap();
+    git(&source, &["init", "--quiet"]);
+    write_template(&source, "greeting", "Hello");
+    git(&source, &["add", "."]);
+    git(&source, &["commit", "--quiet", "-m", "v1"]);
+    git(&source, &["tag", "-a", "v1", "-m", "v1"]);
+
+    let url = format!("file://{}", source.display());
+    let head = RemoteTemplates::git(Some(&source), &["rev-parse", "HEAD"]).unwrap();
+    assert_eq!(
+      RemoteTemplates::remote_commit(&url, "v1").unwrap(),
+      Some(head)
+    );
+  }
+
+  #[test]
+  fn test_offline_serves_stale_pack() {
+    let temp = tempfile::tempdir().unwrap();
+    let source = temp.path().join("source");
+    fs::create_dir_all(&source).unwrap();
+    git(&source, &["init", "--quiet"]);
+    write_template(&source, "greeting", "Hello");
+    git(&source, &["add", "."]);
+    git(&source, &["commit", "--quiet", "-m", "init"]);
+
+    let cache = CacheSettings {
+      directory: temp.path().join("cache").to_string_lossy().to_string(),
+      ttl_seconds: 0,
+      offline: true,
+      ..CacheSettings::default()
+    };
+    let pack = TemplatePack {
+      url: source.to_string_lossy().to_string(),
+      reference: None,
+      namespace: "team".to_string(),
+      path: None,
+    };
+
+    let checkout = RemoteTemplates::new(&cache).unwrap().fetch(&pack).unwrap();
+    fs::remove_dir_all(&source).unwrap();
+
+    let stale = RemoteTemplates::new(&cache).unwrap().fetch(&pack).unwrap();
+    assert_eq!(stale, checkout);
+    assert!(stale.join("greeting.json").exists());
+  }
+}
@@ -0,0 +1,270 @@
+//! Skills for the agents core system
+//!
+//! A skill is a directory named after the skill holding a `SKILL.md` entry with
+//! `name` and `description` front matter, plus any supporting asset files
+//! (scripts, references, examples) the entry points to:
+//!
+//! ```text
+//! __ai/skills/
+//!   database/
+//!     SKILL.md
+//!     schema.sql
+//!     references/naming.md
+//! ```
+
+use super::error::{AgentError, Result};
+use super::frontmatter;
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use std::collections::{BTreeMap, HashMap};
+use std::path::{Path, PathBuf};
+
+/// File name of the entry of a skill directory
+pub const SKILL_ENTRY: &str = "SKILL.md";
+
+/// Maximum length of a skill name
+const MAX_NAME_LENGTH: usize = 64;
+
+/// Maximum length of a skill description
+const MAX_DESCRIPTION_LENGTH: usize = 1024;
+
+/// Front matter of a skill entry
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct SkillFrontMatter {
+  /// Skill name, lowercase letters, digits and hyphens
+  #[serde(default)]
+  pub name: String,
+  /// What the skill does and when to use it
+  #[serde(default)]
+  pub description: String,
+  /// Further keys, kept as written
+  #[serde(flatten)]
+  pub extra: BTreeMap<String, serde_norway::Value>,
+}
+
+impl SkillFrontMatter {
+  /// Check the required `name` and `description` keys
+  pub fn validate(&self) -> Result<()> {
+    if self.name.is_empty() {
+      return Err(AgentError::Template(
+        "Skill front matter is missing 'name'".to_string(),
+      ));
+    }
+    if self.name.len() > MAX_NAME_LENGTH
+      || !self
+        .name
+        .chars()
+        .all(|c| c.is_ascii_lowercase() || c.is_ascii_digit() || c == '-')
+      || self.name.starts_with('-')
+      || self.name.ends_with('-')
+    {
+      return Err(AgentError::Template(format!(
+        "Invalid skill name '{}': use at most {} lowercase letters, digits and hyphens",
+        self.name, MAX_NAME_LENGTH
+      )));
+    }
+    if self.description.trim().is_empty() {
+      return Err(AgentError::Template(format!(
+        "Skill '{}' front matter is missing 'description'",
+        self.name
+      )));
+    }
+    if self.description.chars().count() > MAX_DESCRIPTION_LENGTH {
+      return Err(AgentError::Template(format!(
+        "Description of skill '{}' exceeds {} characters",
+        self.name, MAX_DESCRIPTION_LENGTH
+      )));
+    }
+    Ok(())
+  }
+}
+
+/// Skill loaded from a skill directory
+#[derive(Debug, Clone, PartialEq)]
+pub struct Skill {
+  /// Validated front matter of the entry
+  pub front_matter: SkillFrontMatter,
+  /// Body of the entry after the front matter
+  pub body: String,
+  /// Skill directory
+  pub directory: PathBuf,
+  /// Asset files, relative to the skill directory and sorted
+  pub assets: Vec<PathBuf>,
+}
+
+impl Skill {
+  /// Load and validate the skill of a directory
+  pub fn load<P: AsRef<Path>>(directory: P) -> Result<Self> {
+    let directory = directory.as_ref();
+    let entry = directory.join(SKILL_ENTRY);
+    let content = std::fs::read_to_string(&entry).map_err(|e| {
+      AgentError::Storage(format!("Failed to read skill {}: {}", entry.display(), e))
+    })?;
+    let (front_matter, body) =
+      parse(&content).map_err(|e| AgentError::Template(format!("{}: {}", entry.display(), e)))?;
+
+    let mut assets = Vec::new();
+    collect_assets(directory, directory, &mut assets)?;
+    assets.sort();
+
+    Ok(Self {
+      front_matter,
+      body,
+      directory: directory.to_path_buf(),
+      assets,
+    })
+  }
+
+  /// Skill name
+  pub fn name(&self) -> &str {
+    &self.front_matter.name
+  }
+
+  /// Every file of the skill, the entry first, relative to the skill directory
+  pub fn files(&self) -> Vec<PathBuf> {
+    std::iter::once(PathBuf::from(SKILL_ENTRY))
+      .chain(self.assets.iter().cloned())
+      .collect()
+  }
+}
+
+/// Parse and validate a skill entry, returning its front matter and body
+pub fn parse(content: &str) -> Result<(SkillFrontMatter, String)> {
+  let (front_matter, body): (SkillFrontMatter, String) = frontmatter::parse(content)?;
+  front_matter.validate()?;
+  Ok((front_matter, body))
+}
+
+/// Build the entry document of a composed skill
+///
+/// Rendered output carrying its own front matter is validated as is; otherwise
+/// the front matter is built from the `name` and `description` variables.
+pub fn compose_entry(
+  rendered: &str,
+  variables: &HashMap<String, Value>,
+) -> Result<(SkillFrontMatter, String)> {
+  if frontmatter::split(rendered).is_some() {
+    let (front_matter, _) = parse(rendered)?;
+    return Ok((front_matter, rendered.to_string()));
+  }
+
+  let variable = |key: &str| {
+    variables
+      .get(key)
+      .and_then(Value::as_str)
+      .unwrap_or_default()
+      .trim()
+      .to_string()
+  };
+  let front_matter = SkillFrontMatter {
+    name: variable("name"),
+    description: variable("description"),
+    extra: BTreeMap::new(),
+  };
+  front_matter.validate()?;
+  let document = frontmatter::render(&front_matter, rendered)?;
+  Ok((front_matter, document))
+}
+
+/// Path of the entry of a skill directory, relative to the skills directory
+pub fn entry_file_name(directory: &str) -> String {
+  format!("{}/{}", directory.trim_end_matches('/'), SKILL_ENTRY)
+}
+
+/// Load every skill directory under a skills directory, sorted by directory
+///
+/// Directories without an entry are skipped; invalid entries are errors.
+pub fn discover<P: AsRef<Path>>(root: P) -> Result<Vec<Skill>> {
+  let root = root.as_ref();
+  if !root.exists() {
+    return Ok(Vec::new());
+  }
+
+  let mut directories = Vec::new();
+  for entry in std::fs::read_dir(root)
+    .map_err(|e| AgentError::Storage(format!("Failed to read skills directory: {}", e)))?
+  {
+    let entry =
+      entry.map_err(|e| AgentError::Storage(format!("Failed to read directory entry: {}", e)))?;
+    let path = entry.path();
+    if path.join(SKILL_ENTRY).is_file() {
+      directories.push(path);
+    }
+  }
+  directories.sort();
+
+  directories.iter().map(Skill::load).collect()
+}
+
+fn collect_assets(root: &Path, dir: &Path, assets: &mut Vec<PathBuf>) -> Result<()> {
+  for entry in std::fs::read_dir(dir)
+    .map_err(|e| AgentError::Storage(format!("Failed to read skill directory: {}", e)))?
+  {
+    let entry =
+      entry.map_err(|e| AgentError::Storage(format!("Failed to read directory entry: {}", e)))?;
+    let path = entry.path();
+    if path.is_dir() {
+      collect_assets(root, &path, assets)?;
+    } else if let Ok(relative) = path.strip_prefix(root)
+      && relative != Path::new(SKILL_ENTRY)
+    {
+      assets.push(relative.to_path_buf());
+    }
+  }
+  Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  #[test]
+  fn test_load_skill_with_assets() {
+    let temp = tempfile::tempdir().unwrap();
+    let dir = temp.path().join("database");
+    std::fs::create_dir_all(dir.join("references")).unwrap();
+    std::fs::write(
+      dir.join(SKILL_ENTRY),
+      "---\nname: database-standard\ndescription: Database design standards\nlicense: MIT\n---\n\nUse PostgreSQL.\n",
+    )
+    .unwrap();
+    std::fs::write(dir.join("schema.sql"), "create table t ();\n").unwrap();
+    std::fs::write(dir.join("references/naming.md"), "# Naming\n").unwrap();
+    std::fs::create_dir_all(temp.path().join("empty")).unwrap();
+
+    let skills = discover(temp.path()).unwrap();
+    assert_eq!(skills.len(), 1);
+    let skill = &skills[0];
+    assert_eq!(skill.name(), "database-standard");
+    assert_eq!(skill.front_matter.extra["license"], "MIT");
+    assert_eq!(
+      skill.files(),
+      vec![
+        PathBuf::from(SKILL_ENTRY),
+        PathBuf::from("references/naming.md"),
+        PathBuf::from("schema.sql"),
+      ]
+    );
+  }
+
+  #[test]
+  fn test_front_matter_validation() {
+    assert!(parse("---\nname: eslint-fix\ndescription: Fix lint errors\n---\n").is_ok());
+    let missing = parse("---\nname: eslint-fix\n---\n").unwrap_err();
+    assert!(missing.to_string().contains("'description'"));
+    assert!(parse("---\ndescription: Fix lint errors\n---\n").is_err());
+    assert!(parse("---\nname: Eslint Fix\ndescription: Fix\n---\n").is_err());
+    assert!(parse("# No front matter\n").is_err());
+
+    let variables = HashMap::from([
+      ("name".to_string(), Value::from("timestamp")),
+      ("description".to_string(), Value::from("Format timestamps")),
+    ]);
+    let (front_matter, document) = compose_entry("# Timestamp\n", &variables).unwrap();
+    assert_eq!(front_matter.name, "timestamp");
+    assert_eq!(
+      document,
+      "---\nname: timestamp\ndescription: Format timestamps\n---\n# Timestamp\n"
+    );
+  }
+}
@@ -0,0 +1,238 @@
+//! Snapshot tests for templates in the agents core system
+//!
+//! Fixtures live next to the templates they test: `name.test.json` holds the
+//! variables to render the template of the file `name.md` (or `name.json`) with,
+//! and `name.expected.md` the expected output. Running the snapshots compares the
+//! rendered output with the expected file and reports a unified diff on mismatch.
+
+use super::error::{AgentError, Result};
+use super::storage;
+use super::template::{EXPECTED_SUFFIX, FIXTURE_SUFFIX, TemplateContext, TemplateRegistry};
+use similar::TextDiff;
+use std::collections::HashMap;
+use std::fmt;
+use std::path::{Path, PathBuf};
+
+/// Outcome of one snapshot
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum SnapshotStatus {
+  /// The output matches the expected file
+  Passed,
+  /// The output differs, with the unified diff from expected to actual
+  Failed(String),
+  /// The expected file does not exist yet
+  Missing,
+  /// The expected file was written with the current output
+  Updated,
+  /// The fixture could not be read or rendered
+  Error(String),
+}
+
+/// Result of running the snapshot of one fixture
+#[derive(Debug, Clone)]
+pub struct SnapshotResult {
+  pub template: String,
+  pub fixture: PathBuf,
+  pub status: SnapshotStatus,
+}
+
+/// Results of running the snapshots of one or more directories
+#[derive(Debug, Clone, Default)]
+pub struct SnapshotReport {
+  pub results: Vec<SnapshotResult>,
+}
+
+impl SnapshotReport {
+  /// Check whether every snapshot passed or was updated
+  pub fn is_success(&self) -> bool {
+    self
+      .results
+      .iter()
+      .all(|r| matches!(r.status, SnapshotStatus::Passed | SnapshotStatus::Updated))
+  }
+
+  /// Panic with the report when a snapshot failed, for use in integration tests
+  pub fn assert_success(&self) {
+    assert!(self.is_success(), "template snapshots failed:\n{}", self);
+  }
+}
+
+impl fmt::Display for SnapshotReport {
+  fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+    for result in &self.results {
+      let fixture = result.fixture.display();
+      match &result.status {
+        SnapshotStatus::Passed => writeln!(f, "PASS    {} ({})", result.template, fixture)?,
+        SnapshotStatus::Updated => writeln!(f, "UPDATED {} ({})", result.template, fixture)?,
+        SnapshotStatus::Missing => writeln!(
+          f,
+          "MISSING {} ({}): run with --update to create the snapshot",
+          result.template, fixture
+        )?,
+        SnapshotStatus::Error(error) => {
+          writeln!(f, "ERROR   {} ({}): {}", result.template, fixture, error)?
+        }
+        SnapshotStatus::Failed(diff) => {
+          writeln!(f, "FAIL    {} ({})", result.template, fixture)?;
+          write!(f, "{}", diff)?;
+        }
+      }
+    }
+    let passed = self
+      .results
+      .iter()
+      .filter(|r| matches!(r.status, SnapshotStatus::Passed | SnapshotStatus::Updated))
+      .count();
+    write!(f, "{} of {} snapshot(s) passed", passed, self.results.len())
+  }
+}
+
+/// Render every fixture of a directory and compare it with its expected output
+///
+/// The templates must already be registered. With `update`, mismatching and
+/// missing expected files are written with the current output instead.
+pub fn run_snapshots<P: AsRef<Path>>(
+  registry: &TemplateRegistry,
+  dir: P,
+  update: bool,
+) -> Result<SnapshotReport> {
+  let dir = dir.as_ref();
+  let mut report = SnapshotReport::default();
+  if !dir.exists() {
+    return Ok(report);
+  }
+
+  let mut fixtures = Vec::new();
+  for entry in std::fs::read_dir(dir)
+    .map_err(|e| AgentError::Storage(format!("Failed to read templates directory: {}", e)))?
+  {
+    let entry =
+      entry.map_err(|e| AgentError::Storage(format!("Failed to read directory entry: {}", e)))?;
+    let path = entry.path();
+    if let Some(stem) = path
+      .file_name()
+      .and_then(|s| s.to_str())
+      .and_then(|name| name.strip_suffix(FIXTURE_SUFFIX))
+    {
+      fixtures.push((stem.to_string(), path));
+    }
+  }
+  fixtures.sort();
+
+  for (stem, fixture) in fixtures {
+    let template = template_spec(registry, dir, &stem);
+    let status = match render_fixture(registry, &template, &fixture) {
+      Ok(actual) => {
+        let expected_path = dir.join(format!("{}{}", stem, EXPECTED_SUFFIX));
+        compare(&expected_path, &actual, update)?
+      }
+      Err(error) => SnapshotStatus::Error(error.to_string()),
+    };
+    report.results.push(SnapshotResult {
+      template,
+      fixture,
+      status,
+    });
+  }
+
+  Ok(report)
+}
+
+/// Address the template defined by the file `stem` at its exact version, falling
+/// back to the stem itself when there is no such file
+fn template_spec(registry: &TemplateRegistry, dir: &Path, stem: &str) -> String {
+  ["md", "json"]
+    .iter()
+    .map(|extension| dir.join(format!("{}.{}", stem, extension)))
+    .filter(|path| path.is_file())
+    .find_map(|path| registry.load_template_from_file(path).ok())
+    .map(|template| format!("{}@{}", template.name, template.version))
+    .unwrap_or_else(|| stem.to_string())
+}
+
+fn render_fixture(registry: &TemplateRegistry, template: &str, fixture: &Path) -> Result<String> {
+  let variables: HashMap<String, serde_json::Value> = TemplateRegistry::load_variables(fixture)?;
+  let context = TemplateContext {
+    variables,
+    metadata: HashMap::new(),
+  };
+  registry.render_template(template, &context)
+}
+
+fn compare(expected_path: &Path, actual: &str, update: bool) -> Result<SnapshotStatus> {
+  let expected = match std::fs::read_to_string(expected_path) {
+    Ok(expected) => Some(expected),
+    Err(error) if error.kind() == std::io::ErrorKind::NotFound => None,
+    Err(error) => {
+      return Err(AgentError::Storage(format!(
+        "Failed to read snapshot {}: {}",
+        expected_path.display(),
+        error
+      )));
+    }
+  };
+
+  if expected.as_deref() == Some(actual) {
+    return Ok(SnapshotStatus::Passed);
+  }
+  if update {
+    storage::write_atomic(expected_path, actual.as_bytes())?;
+    return Ok(SnapshotStatus::Updated);
+  }
+
+  Ok(match expected {
+    None => SnapshotStatus::Missing,
+    Some(expected) => SnapshotStatus::Failed(
+      TextDiff::from_lines(expected.as_str(), actual)
+        .unified_diff()
+        .header(&expected_path.display().to_string(), "actual")
+        .to_string(),
+    ),
+  })
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+  use crate::modules::config::PromptTemplate;
+
+  #[test]
+  fn test_snapshots_diff_and_update() {
+    let temp = tempfile::tempdir().unwrap();
+    let dir = temp.path();
+    let mut registry = TemplateRegistry::new().unwrap();
+    let template = PromptTemplate {
+      name: "greeting".to_string(),
+      content: "Hello {{who}}\nBye\n".to_string(),
+      ..PromptTemplate::default()
+    };
+    registry
+      .save_template_to_file(&template, dir.join("greeting.md"))
+      .unwrap();
+    registry.load_templates_from_directory(dir).unwrap();
+    std::fs::write(dir.join("greeting.test.json"), r#"{"who": "world"}"#).unwrap();
+
+    let missing = run_snapshots(&registry, dir, false).unwrap();
+    assert_eq!(missing.results[0].status, SnapshotStatus::Missing);
+    assert_eq!(missing.results[0].template, "greeting@1.0.0");
+
+    std::fs::write(dir.join("greeting.expected.md"), "Hello there\nBye\n").unwrap();
+    let failed = run_snapshots(&registry, dir, false).unwrap();
+    let SnapshotStatus::Failed(diff) = &failed.results[0].status else {
+      panic!("expected a failure, got {:?}", failed.results[0].status);
+    };
+    assert!(diff.contains("-Hello there\n+Hello world\n Bye\n"));
+    assert!(!failed.is_success());
+
+    run_snapshots(&registry, dir, true)
+      .unwrap()
+      .assert_success();
+    run_snapshots(&registry, dir, false)
+      .unwrap()
+      .assert_success();
+    assert_eq!(
+      std::fs::read_to_string(dir.join("greeting.expected.md")).unwrap(),
+      "Hello world\nBye\n"
+    );
+  }
+}
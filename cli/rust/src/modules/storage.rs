@@ -19,14 +19,79 @@ pub struct BackupInfo {
   pub original_path: String,
   /// Files included in this backup
   pub files: Vec<String>,
+  /// Directories outside the original path included in this backup, absolute
+  #[serde(default)]
+  pub included_directories: Vec<String>,
+  /// Files of the included directories, as absolute paths
+  #[serde(default)]
+  pub included_files: Vec<String>,
   /// Backup description
   pub description: Option<String>,
 }
 
+/// Directory of a backup holding the files of the included directories
+const INCLUDED_DIRECTORY: &str = ".included";
+
+/// File of a backup holding its metadata
+const BACKUP_INFO_FILE: &str = "backup_info.json";
+
+impl BackupInfo {
+  /// Path of an included file within the backup, under the index of its
+  /// included directory so that directories with the same name do not collide
+  fn included_path(&self, file: &Path) -> Option<PathBuf> {
+    self
+      .included_directories
+      .iter()
+      .enumerate()
+      .find_map(|(index, directory)| {
+        file.strip_prefix(directory).ok().map(|relative| {
+          Path::new(INCLUDED_DIRECTORY)
+            .join(index.to_string())
+            .join(relative)
+        })
+      })
+  }
+}
+
+/// Write content to a file atomically through a temporary file and a rename
+pub fn write_atomic<P: AsRef<Path>, C: AsRef<[u8]>>(path: P, content: C) -> Result<()> {
+  let full_path = path.as_ref();
+
+  // Ensure parent directory exists
+  if let Some(parent) = full_path.parent()
+    && !parent.as_os_str().is_empty()
+  {
+    fs::create_dir_all(parent)
+      .map_err(|e| AgentError::Storage(format!("Failed to create parent directory: {}", e)))?;
+  }
+
+  // Write to temporary file first
+  let temp_path = full_path.with_extension(format!(
+    "tmp.{}",
+    SystemTime::now()
+      .duration_since(UNIX_EPOCH)
+      .map_err(|e| AgentError::Internal(format!("System time error: {}", e)))?
+      .as_nanos()
+  ));
+
+  fs::write(&temp_path, content)
+    .map_err(|e| AgentError::Storage(format!("Failed to write temporary file: {}", e)))?;
+
+  // Atomically rename
+  fs::rename(&temp_path, full_path).map_err(|e| {
+    // Try to clean up temp file if rename fails
+    let _ = fs::remove_file(&temp_path);
+    AgentError::Storage(format!("Failed to rename temporary file: {}", e))
+  })?;
+
+  Ok(())
+}
+
 /// Storage manager for handling file operations
 pub struct StorageManager {
   base_path: PathBuf,
   backup_path: PathBuf,
+  included: Vec<PathBuf>,
 }
 
 impl StorageManager {
@@ -44,9 +109,15 @@ impl StorageManager {
     Ok(Self {
       base_path,
       backup_path,
+      included: Vec::new(),
     })
   }
 
+  /// Include a directory outside the base path in backups
+  pub fn include_directory<P: AsRef<Path>>(&mut self, path: P) {
+    self.included.push(path.as_ref().to_path_buf());
+  }
+
   /// Get the base path
   pub fn base_path(&self) -> &Path {
     &self.base_path
@@ -63,35 +134,7 @@ impl StorageManager {
     path: P,
     content: C,
   ) -> Result<()> {
-    let path = path.as_ref();
-    let full_path = self.base_path.join(path);
-
-    // Ensure parent directory exists
-    if let Some(parent) = full_path.parent() {
-      fs::create_dir_all(parent)
-        .map_err(|e| AgentError::Storage(format!("Failed to create parent directory: {}", e)))?;
-    }
-
-    // Write to temporary file first
-    let temp_path = full_path.with_extension(format!(
-      "tmp.{}",
-      SystemTime::now()
-        .duration_since(UNIX_EPOCH)
-        .map_err(|e| AgentError::Internal(format!("System time error: {}", e)))?
-        .as_nanos()
-    ));
-
-    fs::write(&temp_path, content)
-      .map_err(|e| AgentError::Storage(format!("Failed to write temporary file: {}", e)))?;
-
-    // Atomically rename
-    fs::rename(&temp_path, &full_path).map_err(|e| {
-      // Try to clean up temp file if rename fails
-      let _ = fs::remove_file(&temp_path);
-      AgentError::Storage(format!("Failed to rename temporary file: {}", e))
-    })?;
-
-    Ok(())
+    write_atomic(self.base_path.join(path), content)
   }
 
   /// Read file content
@@ -191,7 +234,7 @@ impl StorageManager {
       }
     }
 
-    Ok(BackupInfo {
+    let mut info = BackupInfo {
       timestamp,
       path: backup_dir.to_string_lossy().to_string(),
       original_path: self.base_path.to_string_lossy().to_string(),
@@ -204,8 +247,41 @@ impl StorageManager {
             .map(|s| s.to_string())
         })
         .collect(),
+      included_directories: Vec::new(),
+      included_files: Vec::new(),
       description,
-    })
+    };
+
+    // Copy the files of the included directories, recording where they came from
+    for dir in &self.included {
+      let dir = std::path::absolute(dir)
+        .map_err(|e| AgentError::Storage(format!("Failed to resolve {}: {}", dir.display(), e)))?;
+      info
+        .included_directories
+        .push(dir.to_string_lossy().to_string());
+      let mut included_files = Vec::new();
+      self.collect_files(&dir, &mut included_files)?;
+      for file in included_files {
+        let Some(relative) = info.included_path(&file) else {
+          continue;
+        };
+        let backup_file_path = backup_dir.join(relative);
+        if let Some(parent) = backup_file_path.parent() {
+          fs::create_dir_all(parent).map_err(|e| {
+            AgentError::Storage(format!("Failed to create backup subdirectory: {}", e))
+          })?;
+        }
+        fs::copy(&file, &backup_file_path)
+          .map_err(|e| AgentError::Storage(format!("Failed to copy file to backup: {}", e)))?;
+        info.included_files.push(file.to_string_lossy().to_string());
+      }
+    }
+
+    let metadata = serde_json::to_string_pretty(&info)
+      .map_err(|e| AgentError::Storage(format!("Failed to serialize backup metadata: {}", e)))?;
+    write_atomic(backup_dir.join(BACKUP_INFO_FILE), metadata)?;
+
+    Ok(info)
   }
 
   /// Restore from a backup
@@ -218,31 +294,38 @@ impl StorageManager {
       )));
     }
 
+    // Read the backup first: a pre-restore backup taken within the same second
+    // reuses its directory
+    let mut restored = Vec::new();
+    for file in &backup.files {
+      restored.push((self.base_path.join(file), backup_path.join(file)));
+    }
+    for file in &backup.included_files {
+      let relative = backup.included_path(Path::new(file)).ok_or_else(|| {
+        AgentError::Storage(format!(
+          "Backed up file {} is outside the included directories",
+          file
+        ))
+      })?;
+      restored.push((PathBuf::from(file), backup_path.join(relative)));
+    }
+    let contents = restored
+      .into_iter()
+      .map(|(target, source)| {
+        fs::read(&source)
+          .map(|content| (target, content))
+          .map_err(|e| AgentError::Storage(format!("Failed to read backup file: {}", e)))
+      })
+      .collect::<Result<Vec<_>>>()?;
+
     // Create a backup of current state before restoring
     self.create_backup(Some(format!(
       "Pre-restore backup before restoring from {}",
       backup.timestamp
     )))?;
 
-    // Remove existing files and restore from backup
-    for file in &backup.files {
-      let current_file_path = self.base_path.join(file);
-      let backup_file_path = backup_path.join(file);
-
-      // Remove existing file if it exists
-      if current_file_path.exists() {
-        fs::remove_file(&current_file_path)
-          .map_err(|e| AgentError::Storage(format!("Failed to remove existing file: {}", e)))?;
-      }
-
-      // Ensure parent directory exists
-      if let Some(parent) = current_file_path.parent() {
-        fs::create_dir_all(parent)
-          .map_err(|e| AgentError::Storage(format!("Failed to create parent directory: {}", e)))?;
-      }
-
-      // Copy from backup
-      fs::copy(&backup_file_path, &current_file_path)
+    for (path, content) in contents {
+      write_atomic(&path, content)
         .map_err(|e| AgentError::Storage(format!("Failed to restore file: {}", e)))?;
     }
 
@@ -271,16 +354,20 @@ impl StorageManager {
           && let Ok(timestamp) = timestamp_str.parse::<u64>()
         {
           // Try to read backup metadata
-          let metadata_path = path.join("backup_info.json");
+          let metadata_path = path.join(BACKUP_INFO_FILE);
           let backup_info = if metadata_path.exists() {
             let content = fs::read_to_string(&metadata_path)
               .map_err(|e| AgentError::Storage(format!("Failed to read backup metadata: {}", e)))?;
             serde_json::from_str(&content)
               .map_err(|e| AgentError::Storage(format!("Failed to parse backup metadata: {}", e)))?
           } else {
-            // Create basic backup info from directory structure
+            // Create basic backup info from directory structure; without
+            // metadata, the origin of included files is unknown and they are
+            // left out
             let mut files = Vec::new();
             self.collect_files(&path, &mut files)?;
+            let included = path.join(INCLUDED_DIRECTORY);
+            files.retain(|f| !f.starts_with(&included));
             BackupInfo {
               timestamp,
               path: path.to_string_lossy().to_string(),
@@ -294,6 +381,8 @@ impl StorageManager {
                     .map(|s| s.to_string())
                 })
                 .collect(),
+              included_directories: Vec::new(),
+              included_files: Vec::new(),
               description: None,
             }
           };
@@ -303,7 +392,7 @@ impl StorageManager {
     }
 
     // Sort by timestamp (newest first)
-    backups.sort_by(|a, b| b.timestamp.cmp(&a.timestamp));
+    backups.sort_by_key(|b| std::cmp::Reverse(b.timestamp));
     Ok(backups)
   }
 
@@ -342,3 +431,60 @@ impl StorageManager {
     Ok(())
   }
 }
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  #[test]
+  fn test_backup_restores_included_directories() {
+    let temp = tempfile::tempdir().unwrap();
+    let skills = temp.path().join("skills");
+    fs::create_dir_all(skills.join("database")).unwrap();
+    fs::write(skills.join("database/SKILL.md"), "original").unwrap();
+    fs::write(skills.join("database/schema.sql"), "create table t ();").unwrap();
+
+    let mut storage = StorageManager::new(temp.path().join("memory")).unwrap();
+    storage.include_directory(&skills);
+    let backup = storage.create_backup(None).unwrap();
+    assert_eq!(backup.included_files.len(), 2);
+
+    fs::write(skills.join("database/SKILL.md"), "changed").unwrap();
+    fs::remove_file(skills.join("database/schema.sql")).unwrap();
+    storage.restore_from_backup(&backup).unwrap();
+    assert_eq!(
+      fs::read_to_string(skills.join("database/SKILL.md")).unwrap(),
+      "original"
+    );
+    assert!(skills.join("database/schema.sql").exists());
+  }
+
+  #[test]
+  fn test_listed_backup_restores_included_files_in_place() {
+    let temp = tempfile::tempdir().unwrap();
+    let project = temp.path().join("project/skills");
+    let shared = temp.path().join("shared/skills");
+    for dir in [&project, &shared] {
+      fs::create_dir_all(dir.join("tsx")).unwrap();
+      fs::write(dir.join("tsx/SKILL.md"), dir.to_string_lossy().as_bytes()).unwrap();
+    }
+
+    let mut storage = StorageManager::new(temp.path().join("memory")).unwrap();
+    storage.include_directory(&project);
+    storage.include_directory(&shared);
+    storage.create_backup(None).unwrap();
+    for dir in [&project, &shared] {
+      fs::remove_dir_all(dir).unwrap();
+    }
+
+    let backup = storage.list_backups().unwrap().remove(0);
+    assert_eq!(backup.included_files.len(), 2);
+    storage.restore_from_backup(&backup).unwrap();
+    for dir in [&project, &shared] {
+      assert_eq!(
+        fs::read_to_string(dir.join("tsx/SKILL.md")).unwrap(),
+        dir.to_string_lossy()
+      );
+    }
+  }
+}
@@ -0,0 +1,223 @@
+//! Claude Code sync target
+//!
+//! | Prompt type | Output |
+//! |-------------|--------|
+//! | User and project memory | Marked sections of `CLAUDE.md` |
+//! | Command | `.claude/commands/<path>.md` |
+//! | Sub-agent | `.claude/agents/<path>.md` |
+//!
+//! Hand-written content of `CLAUDE.md` outside the generated sections is kept.
+//! Commands and sub-agents whose prompt was removed are removed on the next sync.
+
+use super::{CanonicalPrompt, SyncOutput, SyncTarget, merge_sections};
+use crate::modules::config::TemplateType;
+use crate::modules::error::Result;
+use serde_norway::{Mapping, Value};
+use std::path::Path;
+
+/// Directory Claude Code reads commands from
+const COMMANDS_DIRECTORY: &str = ".claude/commands";
+
+/// Directory Claude Code reads sub-agents from
+const AGENTS_DIRECTORY: &str = ".claude/agents";
+
+/// Front matter keys Claude Code reads from commands
+const COMMAND_KEYS: &[&str] = &[
+  "argument-hint",
+  "allowed-tools",
+  "description",
+  "model",
+  "disable-model-invocation",
+];
+
+/// Claude Code target
+#[derive(Debug, Clone, Copy, Default)]
+pub struct ClaudeTarget;
+
+impl SyncTarget for ClaudeTarget {
+  fn id(&self) -> &'static str {
+    "claude"
+  }
+
+  fn description(&self) -> &'static str {
+    "Claude Code: CLAUDE.md, .claude/commands and .claude/agents"
+  }
+
+  fn render(&self, prompts: &[CanonicalPrompt], output: &mut SyncOutput) -> Result<()> {
+    let mut sections = Vec::new();
+    for prompt in prompts {
+      match prompt.r#type {
+        TemplateType::UserMemory | TemplateType::ProjectMemory => {
+          let kind = if prompt.r#type == TemplateType::UserMemory {
+            "user"
+          } else {
+            "project"
+          };
+          sections.push((
+            format!("{}/{}", kind, prompt.path.with_extension("").display()),
+            prompt.body.clone(),
+          ));
+        }
+        TemplateType::Command => {
+          let mut front_matter = Mapping::new();
+          for key in COMMAND_KEYS {
+            if let Some(value) = prompt.get(key) {
+              front_matter.insert((*key).into(), value.clone());
+            }
+          }
+          output.write(
+            Path::new(COMMANDS_DIRECTORY).join(&prompt.path),
+            prompt.render_with(&front_matter)?,
+          )?;
+        }
+        TemplateType::SubAgent => {
+          let front_matter = agent_front_matter(prompt, output);
+          output.write(
+            Path::new(AGENTS_DIRECTORY).join(&prompt.path),
+            prompt.render_with(&front_matter)?,
+          )?;
+        }
+        _ => {}
+      }
+    }
+
+    // Rewritten while it exists so that removed memory prompts drop their sections
+    let existing = output.existing("CLAUDE.md");
+    if !sections.is_empty() || existing.is_some() {
+      output.write("CLAUDE.md", merge_sections(existing.as_deref(), &sections))?;
+    }
+    Ok(())
+  }
+
+  fn prunes(&self, path: &Path) -> bool {
+    path.starts_with(COMMANDS_DIRECTORY) || path.starts_with(AGENTS_DIRECTORY)
+  }
+}
+
+/// Front matter of a sub-agent, `tools` lists joined into the comma separated form
+fn agent_front_matter(prompt: &CanonicalPrompt, output: &mut SyncOutput) -> Mapping {
+  let mut front_matter = Mapping::new();
+  front_matter.insert(
+    "name".into(),
+    prompt.get_str("name").unwrap_or(&prompt.name).into(),
+  );
+  match prompt.get("description") {
+    Some(description) => {
+      front_matter.insert("description".into(), description.clone());
+    }
+    None => output.warn(format!(
+      "sub-agent {} has no description, Claude Code will not delegate to it",
+      prompt.path.display()
+    )),
+  }
+  if let Some(tools) = prompt.get("tools") {
+    let tools = match tools {
+      Value::Sequence(tools) => tools
+        .iter()
+        .filter_map(Value::as_str)
+        .collect::<Vec<_>>()
+        .join(", ")
+        .into(),
+      tools => tools.clone(),
+    };
+    front_matter.insert("tools".into(), tools);
+  }
+  if let Some(model) = prompt.get("model") {
+    front_matter.insert("model".into(), model.clone());
+  }
+  front_matter
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+  use crate::modules::sync::{SyncManifest, apply};
+
+  #[test]
+  fn test_claude_files() {
+    let temp = tempfile::tempdir().unwrap();
+    std::fs::write(
+      temp.path().join("CLAUDE.md"),
+      "# Notes\n\nHand written.\n\n<!-- agents:begin user/old -->\nstale\n<!-- agents:end user/old -->\n\nFooter\n",
+    )
+    .unwrap();
+    let prompts = [
+      CanonicalPrompt::parse(TemplateType::UserMemory, "global.md", "Be brief.\n").unwrap(),
+      CanonicalPrompt::parse(
+        TemplateType::ProjectMemory,
+        "cli.md",
+        "---\ntitle: CLI\n---\n\nUse pnpm.\n",
+      )
+      .unwrap(),
+      CanonicalPrompt::parse(
+        TemplateType::Command,
+        "pe/compile.md",
+        "---\nargument-hint: src_file\ndescription: Compile\nauto_execution_mode: 3\n---\n\nCompile $1\n",
+      )
+      .unwrap(),
+      CanonicalPrompt::parse(
+        TemplateType::SubAgent,
+        "reviewer.md",
+        "---\ntools: [Read, Grep]\nmodel: sonnet\n---\n\nReview code.\n",
+      )
+      .unwrap(),
+    ];
+
+    let mut output = SyncOutput::new(temp.path());
+    ClaudeTarget.render(&prompts, &mut output).unwrap();
+
+    assert_eq!(
+      output.file_str("CLAUDE.md"),
+      "# Notes\n\nHand written.\n\n<!-- agents:begin user/global -->\nBe brief.\n<!-- agents:end user/global -->\n\n<!-- agents:begin project/cli -->\nUse pnpm.\n<!-- agents:end project/cli -->\n\nFooter\n"
+    );
+    assert_eq!(
+      output.file_str(".claude/commands/pe/compile.md"),
+      "---\nargument-hint: src_file\ndescription: Compile\n---\n\nCompile $1\n"
+    );
+    assert_eq!(
+      output.file_str(".claude/agents/reviewer.md"),
+      "---\nname: reviewer\ntools: Read, Grep\nmodel: sonnet\n---\n\nReview code.\n"
+    );
+    assert_eq!(output.warnings().len(), 1);
+  }
+
+  #[test]
+  fn test_removed_prompts_are_pruned() {
+    let temp = tempfile::tempdir().unwrap();
+    let command =
+      |name: &str| CanonicalPrompt::parse(TemplateType::Command, name, "Run.\n").unwrap();
+    let memory =
+      CanonicalPrompt::parse(TemplateType::ProjectMemory, "cli.md", "Use pnpm.\n").unwrap();
+    let mut manifest = SyncManifest::default();
+
+    let mut output = SyncOutput::new(temp.path());
+    ClaudeTarget
+      .render(
+        &[memory, command("build.md"), command("test.md")],
+        &mut output,
+      )
+      .unwrap();
+    apply(&ClaudeTarget, &mut output, &mut manifest, false).unwrap();
+    std::fs::write(
+      temp.path().join("CLAUDE.md"),
+      format!("# Notes\n\n{}", output.file_str("CLAUDE.md")),
+    )
+    .unwrap();
+
+    let mut output = SyncOutput::new(temp.path());
+    ClaudeTarget
+      .render(&[command("build.md")], &mut output)
+      .unwrap();
+    let changes = apply(&ClaudeTarget, &mut output, &mut manifest, false).unwrap();
+
+    assert_eq!(
+      changes.removed,
+      vec![temp.path().join(".claude/commands/test.md")]
+    );
+    assert!(temp.path().join(".claude/commands/build.md").exists());
+    assert_eq!(
+      std::fs::read_to_string(temp.path().join("CLAUDE.md")).unwrap(),
+      "# Notes\n"
+    );
+  }
+}
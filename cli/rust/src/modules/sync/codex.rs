@@ -0,0 +1,232 @@
+//! Codex sync target
+//!
+//! Codex reads `AGENTS.md` files hierarchically, from the project root down to the
+//! directory it works in. Project memory and rule prompts become marked sections of
+//! the `AGENTS.md` of the directory their `globs` point to: `cli/**/*` lands in
+//! `cli/AGENTS.md`, while unscoped prompts and globs such as `**` land in the root
+//! file. Codex has no on-demand instructions, so manual and model-decision prompts
+//! are skipped. A prompt goes to the outermost of its directories only, and a section whose
+//! content a parent file already holds is left out of the child.
+//!
+//! Files written on the previous sync are merged again to drop the sections of
+//! removed or rescoped prompts, and removed once nothing but those sections is left.
+
+use super::{CanonicalPrompt, Scope, SyncOutput, SyncTarget, merge_sections};
+use crate::modules::config::TemplateType;
+use crate::modules::error::Result;
+use std::collections::{BTreeMap, HashSet};
+use std::path::{Path, PathBuf};
+
+/// File name Codex reads its instructions from
+const AGENTS_FILE: &str = "AGENTS.md";
+
+/// Codex target
+#[derive(Debug, Clone, Copy, Default)]
+pub struct CodexTarget;
+
+impl SyncTarget for CodexTarget {
+  fn id(&self) -> &'static str {
+    "codex"
+  }
+
+  fn description(&self) -> &'static str {
+    "Codex: AGENTS.md at the root and in the directories prompts are scoped to"
+  }
+
+  fn render(&self, prompts: &[CanonicalPrompt], output: &mut SyncOutput) -> Result<()> {
+    let mut files: BTreeMap<PathBuf, Vec<(String, String)>> = BTreeMap::new();
+    for prompt in prompts {
+      let kind = match prompt.r#type {
+        TemplateType::ProjectMemory => "project",
+        TemplateType::Rule => "rule",
+        _ => continue,
+      };
+      let directories = match prompt.scope() {
+        Ok(Scope::Always) => Vec::new(),
+        Ok(Scope::Glob(globs)) => globs
+          .iter()
+          .map(|glob| glob_directory(glob, output.root()))
+          .collect(),
+        Ok(scope) => {
+          output.skip(prompt, format!("Codex has no {} scope", scope));
+          continue;
+        }
+        Err(error) => {
+          output.skip(prompt, error.to_string());
+          continue;
+        }
+      };
+      for directory in outermost(directories) {
+        files.entry(directory).or_default().push((
+          format!("{}/{}", kind, prompt.path.with_extension("").display()),
+          prompt.body.clone(),
+        ));
+      }
+    }
+    for path in output.previous() {
+      if path.file_name() == Some(AGENTS_FILE.as_ref()) {
+        let directory = path.parent().unwrap_or(Path::new("")).to_path_buf();
+        files.entry(directory).or_default();
+      }
+    }
+
+    for (directory, sections) in &files {
+      let inherited: HashSet<&str> = files
+        .iter()
+        .filter(|(parent, _)| *parent != directory && directory.starts_with(parent))
+        .flat_map(|(_, sections)| sections.iter().map(|(_, body)| body.trim()))
+        .collect();
+      let sections: Vec<(String, String)> = sections
+        .iter()
+        .filter(|(_, body)| !inherited.contains(body.trim()))
+        .cloned()
+        .collect();
+
+      let path = directory.join(AGENTS_FILE);
+      let existing = output.existing(&path);
+      if sections.is_empty() && existing.is_none() {
+        continue;
+      }
+      let merged = merge_sections(existing.as_deref(), &sections);
+      // Left out so that a file holding generated sections only is pruned
+      if !merged.trim().is_empty() {
+        output.write(path, merged)?;
+      }
+    }
+    Ok(())
+  }
+
+  fn prunes(&self, path: &Path) -> bool {
+    path.file_name() == Some(AGENTS_FILE.as_ref())
+  }
+}
+
+/// Directory a glob is rooted at, its leading components without wildcards
+fn glob_directory(glob: &str, root: &Path) -> PathBuf {
+  let components: Vec<&str> = glob
+    .split('/')
+    .filter(|part| !part.is_empty() && *part != ".")
+    .collect();
+  let literal = components
+    .iter()
+    .take_while(|part| !part.contains(['*', '?', '[', '{']))
+    .count();
+
+  let mut directory: PathBuf = components[..literal].iter().collect();
+  // A glob without wildcards may name a single file
+  if literal == components.len() && !root.join(&directory).is_dir() {
+    directory.pop();
+  }
+  directory
+}
+
+/// Drop the directories nested in another one, the root when there are none
+fn outermost(mut directories: Vec<PathBuf>) -> Vec<PathBuf> {
+  if directories.is_empty() {
+    return vec![PathBuf::new()];
+  }
+  directories.sort();
+  directories.dedup();
+  let all = directories.clone();
+  directories.retain(|directory| {
+    !all
+      .iter()
+      .any(|parent| parent != directory && directory.starts_with(parent))
+  });
+  directories
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+  use crate::modules::sync::{SyncManifest, apply};
+
+  #[test]
+  fn test_nested_agents_files() {
+    let temp = tempfile::tempdir().unwrap();
+    std::fs::create_dir_all(temp.path().join("cli")).unwrap();
+    let prompts = [
+      CanonicalPrompt::parse(
+        TemplateType::ProjectMemory,
+        "root.md",
+        "Repository guide.\n",
+      )
+      .unwrap(),
+      CanonicalPrompt::parse(
+        TemplateType::Rule,
+        "cli.md",
+        "---\nglobs: cli/**/*, cli/src/*.rs\n---\n\nUse cargo.\n",
+      )
+      .unwrap(),
+      CanonicalPrompt::parse(
+        TemplateType::Rule,
+        "uicontrol.md",
+        "---\nglobs: [uicontrol/**/*]\n---\n\nUse pnpm.\n",
+      )
+      .unwrap(),
+      CanonicalPrompt::parse(
+        TemplateType::Rule,
+        "copy.md",
+        "---\nglobs: cli/package.json\n---\n\nRepository guide.\n",
+      )
+      .unwrap(),
+    ];
+
+    let mut output = SyncOutput::new(temp.path());
+    CodexTarget.render(&prompts, &mut output).unwrap();
+
+    assert_eq!(
+      output.file_str("AGENTS.md"),
+      "<!-- agents:begin project/root -->\nRepository guide.\n<!-- agents:end project/root -->\n"
+    );
+    // The copy of the root guide is not repeated in the nested file
+    assert_eq!(
+      output.file_str("cli/AGENTS.md"),
+      "<!-- agents:begin rule/cli -->\nUse cargo.\n<!-- agents:end rule/cli -->\n"
+    );
+    assert!(output.file_str("uicontrol/AGENTS.md").contains("Use pnpm."));
+    assert_eq!(output.files().len(), 3);
+  }
+
+  #[test]
+  fn test_rescoped_rules_leave_their_directory() {
+    let temp = tempfile::tempdir().unwrap();
+    std::fs::create_dir_all(temp.path().join("cli")).unwrap();
+    std::fs::create_dir_all(temp.path().join("web")).unwrap();
+    std::fs::write(temp.path().join("web/AGENTS.md"), "# Web\n").unwrap();
+    let rule = |globs: &str| {
+      CanonicalPrompt::parse(
+        TemplateType::Rule,
+        "style.md",
+        &format!("---\nglobs: {}\n---\n\nFollow the style.\n", globs),
+      )
+      .unwrap()
+    };
+    let mut manifest = SyncManifest::default();
+
+    let mut output = SyncOutput::new(temp.path()).with_manifest(&manifest, "codex");
+    CodexTarget.render(&[rule("cli/**")], &mut output).unwrap();
+    apply(&CodexTarget, &mut output, &mut manifest, false).unwrap();
+    assert!(temp.path().join("cli/AGENTS.md").exists());
+
+    let mut output = SyncOutput::new(temp.path()).with_manifest(&manifest, "codex");
+    CodexTarget.render(&[rule("web/**")], &mut output).unwrap();
+    let changes = apply(&CodexTarget, &mut output, &mut manifest, false).unwrap();
+
+    assert_eq!(changes.removed, vec![temp.path().join("cli/AGENTS.md")]);
+    assert!(!temp.path().join("cli/AGENTS.md").exists());
+    assert_eq!(
+      std::fs::read_to_string(temp.path().join("web/AGENTS.md")).unwrap(),
+      "# Web\n\n<!-- agents:begin rule/style -->\nFollow the style.\n<!-- agents:end rule/style -->\n"
+    );
+
+    // Removing the rule drops its section and keeps the hand-written text
+    let mut output = SyncOutput::new(temp.path()).with_manifest(&manifest, "codex");
+    CodexTarget.render(&[], &mut output).unwrap();
+    apply(&CodexTarget, &mut output, &mut manifest, false).unwrap();
+    assert_eq!(
+      std::fs::read_to_string(temp.path().join("web/AGENTS.md")).unwrap(),
+      "# Web\n"
+    );
+  }
+}
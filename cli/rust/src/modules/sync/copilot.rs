@@ -0,0 +1,164 @@
+//! GitHub Copilot sync target
+//!
+//! | Prompt | Output |
+//! |--------|--------|
+//! | Project memory and rules, `always` scope | Marked sections of `.github/copilot-instructions.md` |
+//! | Project memory and rules, other scopes | `.github/instructions/<path>.instructions.md` |
+//! | Command | `.github/prompts/<path>.prompt.md` |
+//!
+//! Paths are flattened with `_`. Glob scoped instructions get `applyTo`; manual
+//! and model-decision ones are left to be attached by hand or by description.
+//! Nothing else under `.github`, such as issue and pull request templates, is
+//! written or removed.
+
+use super::{CanonicalPrompt, Scope, SyncOutput, SyncTarget, flat_name, merge_sections};
+use crate::modules::config::TemplateType;
+use crate::modules::error::Result;
+use serde_norway::Mapping;
+use std::path::{Path, PathBuf};
+
+/// Repository-wide instructions file
+const INSTRUCTIONS_FILE: &str = ".github/copilot-instructions.md";
+
+/// Directory of path-specific instructions
+const INSTRUCTIONS_DIRECTORY: &str = ".github/instructions";
+
+/// Directory of reusable prompts
+const PROMPTS_DIRECTORY: &str = ".github/prompts";
+
+/// Front matter keys Copilot reads from prompt files
+const PROMPT_KEYS: &[&str] = &["description", "argument-hint", "model", "tools"];
+
+/// GitHub Copilot target
+#[derive(Debug, Clone, Copy, Default)]
+pub struct CopilotTarget;
+
+impl SyncTarget for CopilotTarget {
+  fn id(&self) -> &'static str {
+    "copilot"
+  }
+
+  fn description(&self) -> &'static str {
+    "GitHub Copilot: .github/copilot-instructions.md, instructions and prompts"
+  }
+
+  fn render(&self, prompts: &[CanonicalPrompt], output: &mut SyncOutput) -> Result<()> {
+    let mut sections = Vec::new();
+    for prompt in prompts {
+      match prompt.r#type {
+        TemplateType::ProjectMemory | TemplateType::Rule => {
+          let scope = match prompt.scope() {
+            Ok(scope) => scope,
+            Err(error) => {
+              output.skip(prompt, error.to_string());
+              continue;
+            }
+          };
+          let mut front_matter = Mapping::new();
+          if let Some(description) = prompt.get("description") {
+            front_matter.insert("description".into(), description.clone());
+          }
+          match scope {
+            Scope::Always => {
+              let kind = if prompt.r#type == TemplateType::Rule {
+                "rule"
+              } else {
+                "project"
+              };
+              sections.push((
+                format!("{}/{}", kind, prompt.path.with_extension("").display()),
+                prompt.body.clone(),
+              ));
+              continue;
+            }
+            Scope::Glob(globs) => {
+              front_matter.insert("applyTo".into(), globs.join(",").into());
+            }
+            Scope::Manual | Scope::ModelDecision => {}
+          }
+          output.write(
+            Path::new(INSTRUCTIONS_DIRECTORY).join(suffixed(&prompt.path, "instructions")),
+            prompt.render_with(&front_matter)?,
+          )?;
+        }
+        TemplateType::Command => {
+          let mut front_matter = Mapping::new();
+          for key in PROMPT_KEYS {
+            if let Some(value) = prompt.get(key) {
+              front_matter.insert((*key).into(), value.clone());
+            }
+          }
+          output.write(
+            Path::new(PROMPTS_DIRECTORY).join(suffixed(&prompt.path, "prompt")),
+            prompt.render_with(&front_matter)?,
+          )?;
+        }
+        _ => {}
+      }
+    }
+
+    let existing = output.existing(INSTRUCTIONS_FILE);
+    if !sections.is_empty() || existing.is_some() {
+      output.write(
+        INSTRUCTIONS_FILE,
+        merge_sections(existing.as_deref(), &sections),
+      )?;
+    }
+    Ok(())
+  }
+
+  fn prunes(&self, path: &Path) -> bool {
+    let name = path.to_string_lossy();
+    (path.parent() == Some(Path::new(INSTRUCTIONS_DIRECTORY)) && name.ends_with(".instructions.md"))
+      || (path.parent() == Some(Path::new(PROMPTS_DIRECTORY)) && name.ends_with(".prompt.md"))
+  }
+}
+
+/// Flat file name with a kind before the extension, `pe/compile.md` becoming
+/// `pe_compile.prompt.md`
+fn suffixed(path: &Path, kind: &str) -> PathBuf {
+  flat_name(&path.with_extension(format!("{}.md", kind)))
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  #[test]
+  fn test_copilot_files() {
+    let temp = tempfile::tempdir().unwrap();
+    let prompts = [
+      CanonicalPrompt::parse(TemplateType::ProjectMemory, "root.md", "Guide.\n").unwrap(),
+      CanonicalPrompt::parse(
+        TemplateType::Rule,
+        "lang/rust.md",
+        "---\nglobs: [cli/**/*.rs, \"*.toml\"]\ndescription: Rust\n---\n\nUse cargo.\n",
+      )
+      .unwrap(),
+      CanonicalPrompt::parse(
+        TemplateType::Command,
+        "pe/compile.md",
+        "---\ndescription: Compile\nexecution: turbo\n---\n\nCompile $1\n",
+      )
+      .unwrap(),
+    ];
+
+    let mut output = SyncOutput::new(temp.path());
+    CopilotTarget.render(&prompts, &mut output).unwrap();
+
+    assert_eq!(
+      output.file_str(".github/copilot-instructions.md"),
+      "<!-- agents:begin project/root -->\nGuide.\n<!-- agents:end project/root -->\n"
+    );
+    assert_eq!(
+      output.file_str(".github/instructions/lang_rust.instructions.md"),
+      "---\ndescription: Rust\napplyTo: cli/**/*.rs,*.toml\n---\n\nUse cargo.\n"
+    );
+    assert_eq!(
+      output.file_str(".github/prompts/pe_compile.prompt.md"),
+      "---\ndescription: Compile\n---\n\nCompile $1\n"
+    );
+    assert!(!CopilotTarget.prunes(Path::new(".github/pull_request_template.md")));
+    assert!(CopilotTarget.prunes(Path::new(".github/prompts/pe_compile.prompt.md")));
+  }
+}
@@ -0,0 +1,272 @@
+//! Cursor sync target
+//!
+//! Project memory and rule prompts become `.mdc` project rules in `.cursor/rules/`,
+//! their scope translated to the rule type:
+//!
+//! | Scope | Front matter |
+//! |-------|--------------|
+//! | `always` | `alwaysApply: true` |
+//! | `glob` | `globs` with `alwaysApply: false` |
+//! | `manual` | `alwaysApply: false` |
+//! | `model-decision` | `description` with `alwaysApply: false` |
+//!
+//! A rule larger than the `max_size` setting of the target is split at its
+//! headings into numbered rules sharing the front matter. Rules whose prompt was
+//! removed are removed on the next sync.
+
+use super::{CanonicalPrompt, Scope, SyncOutput, SyncTarget};
+use crate::modules::config::TemplateType;
+use crate::modules::error::Result;
+use std::path::{Path, PathBuf};
+
+/// Directory Cursor reads project rules from
+const RULES_DIRECTORY: &str = ".cursor/rules";
+
+/// Rule size in characters when the target has no `max_size`
+const DEFAULT_MAX_SIZE: usize = 50_000;
+
+/// Cursor target
+#[derive(Debug, Clone, Copy, Default)]
+pub struct CursorTarget;
+
+impl SyncTarget for CursorTarget {
+  fn id(&self) -> &'static str {
+    "cursor"
+  }
+
+  fn description(&self) -> &'static str {
+    "Cursor: .cursor/rules/*.mdc with rule types"
+  }
+
+  fn render(&self, prompts: &[CanonicalPrompt], output: &mut SyncOutput) -> Result<()> {
+    let max_size = output.settings().max_size.unwrap_or(DEFAULT_MAX_SIZE);
+    for prompt in prompts {
+      if !matches!(
+        prompt.r#type,
+        TemplateType::ProjectMemory | TemplateType::Rule
+      ) {
+        continue;
+      }
+      let scope = match prompt.scope() {
+        Ok(scope) => scope,
+        Err(error) => {
+          output.skip(prompt, error.to_string());
+          continue;
+        }
+      };
+
+      let front_matter = front_matter(prompt, &scope, output)?;
+      let budget = max_size.saturating_sub(front_matter.chars().count());
+      if budget == 0 {
+        output.skip(
+          prompt,
+          format!("its front matter does not fit in {} characters", max_size),
+        );
+        continue;
+      }
+
+      let path = Path::new(RULES_DIRECTORY).join(prompt.path.with_extension("mdc"));
+      let parts = split(&prompt.body, budget);
+      if parts.len() == 1 {
+        output.write(path, format!("{}{}", front_matter, prompt.body))?;
+        continue;
+      }
+      for (index, part) in parts.iter().enumerate() {
+        output.write(
+          part_path(&path, index + 1),
+          format!("{}{}", front_matter, part),
+        )?;
+      }
+    }
+    Ok(())
+  }
+
+  fn prunes(&self, path: &Path) -> bool {
+    path.starts_with(RULES_DIRECTORY) && path.extension().is_some_and(|e| e == "mdc")
+  }
+}
+
+/// Front matter of a rule
+///
+/// Written by hand since Cursor reads `globs` as a bare comma separated list,
+/// which YAML would quote.
+fn front_matter(
+  prompt: &CanonicalPrompt,
+  scope: &Scope,
+  output: &mut SyncOutput,
+) -> Result<String> {
+  let description = match prompt.get("description") {
+    Some(description) => serde_norway::to_string(description)?.trim_end().to_string(),
+    None if *scope == Scope::ModelDecision => {
+      output.warn(format!(
+        "{} has the model-decision scope but no description, Cursor will not pick it",
+        prompt.path.display()
+      ));
+      String::new()
+    }
+    None => String::new(),
+  };
+  let globs = match scope {
+    Scope::Glob(globs) => globs.join(","),
+    _ => String::new(),
+  };
+  Ok(format!(
+    "---\ndescription: {}\nglobs: {}\nalwaysApply: {}\n---\n",
+    description,
+    globs,
+    *scope == Scope::Always
+  ))
+}
+
+/// Split a body into parts of at most `budget` characters, at headings outside
+/// code fences where possible, then at lines
+fn split(body: &str, budget: usize) -> Vec<String> {
+  if body.chars().count() <= budget {
+    return vec![body.to_string()];
+  }
+
+  let mut sections: Vec<String> = Vec::new();
+  let mut fenced = false;
+  for line in body.split_inclusive('\n') {
+    let trimmed = line.trim_start();
+    if trimmed.starts_with("```") || trimmed.starts_with("~~~") {
+      fenced = !fenced;
+    } else if !fenced && trimmed.starts_with('#') {
+      sections.push(String::new());
+    }
+    match sections.last_mut() {
+      Some(section) => section.push_str(line),
+      None => sections.push(line.to_string()),
+    }
+  }
+
+  let mut pieces = Vec::new();
+  for section in sections {
+    if section.chars().count() <= budget {
+      pieces.push(section);
+      continue;
+    }
+    for line in section.split_inclusive('\n') {
+      let chars: Vec<char> = line.chars().collect();
+      pieces.extend(chars.chunks(budget).map(|chunk| chunk.iter().collect()));
+    }
+  }
+
+  let mut parts: Vec<String> = Vec::new();
+  let mut size = 0;
+  for piece in pieces {
+    let piece_size = piece.chars().count();
+    match parts.last_mut() {
+      Some(part) if size + piece_size <= budget => {
+        part.push_str(&piece);
+        size += piece_size;
+      }
+      _ => {
+        parts.push(piece);
+        size = piece_size;
+      }
+    }
+  }
+  parts
+}
+
+/// Path of a numbered part of a rule, `rust.mdc` becoming `rust-2.mdc`
+fn part_path(path: &Path, number: usize) -> PathBuf {
+  let stem = path
+    .file_stem()
+    .and_then(|s| s.to_str())
+    .unwrap_or_default();
+  path.with_file_name(format!("{}-{}.mdc", stem, number))
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+  use crate::modules::sync::{SyncManifest, apply};
+
+  #[test]
+  fn test_rule_types_and_splitting() {
+    let prompts = [
+      CanonicalPrompt::parse(TemplateType::ProjectMemory, "root.md", "Guide.\n").unwrap(),
+      CanonicalPrompt::parse(
+        TemplateType::Rule,
+        "cli.md",
+        "---\nglobs: [cli/**/*, \"*.rs\"]\n---\n\nUse cargo.\n",
+      )
+      .unwrap(),
+      CanonicalPrompt::parse(
+        TemplateType::Rule,
+        "review.md",
+        "---\nscope: model-decision\ndescription: Review rules\n---\n\nReview.\n",
+      )
+      .unwrap(),
+      CanonicalPrompt::parse(
+        TemplateType::Rule,
+        "long.md",
+        "---\nscope: manual\n---\n\n# One\n\n```sh\n# not a heading\n```\n\n# Two\n\nText.\n",
+      )
+      .unwrap(),
+    ];
+
+    let temp = tempfile::tempdir().unwrap();
+    let mut output =
+      SyncOutput::new(temp.path()).with_settings(crate::modules::config::TargetSettings {
+        max_size: Some(90),
+        ..Default::default()
+      });
+    CursorTarget.render(&prompts, &mut output).unwrap();
+
+    assert_eq!(
+      output.file_str(".cursor/rules/root.mdc"),
+      "---\ndescription: \nglobs: \nalwaysApply: true\n---\nGuide.\n"
+    );
+    assert!(
+      output
+        .file_str(".cursor/rules/cli.mdc")
+        .contains("globs: cli/**/*,*.rs\nalwaysApply: false\n")
+    );
+    assert!(
+      output
+        .file_str(".cursor/rules/review.mdc")
+        .contains("description: Review rules\n")
+    );
+    assert_eq!(
+      output.file_str(".cursor/rules/long-1.mdc"),
+      "---\ndescription: \nglobs: \nalwaysApply: false\n---\n\n# One\n\n```sh\n# not a heading\n```\n\n"
+    );
+    assert!(
+      output
+        .file_str(".cursor/rules/long-2.mdc")
+        .ends_with("---\n# Two\n\nText.\n")
+    );
+    assert_eq!(output.files().len(), 5);
+    assert!(output.warnings().is_empty());
+  }
+
+  #[test]
+  fn test_removed_prompts_are_pruned() {
+    let temp = tempfile::tempdir().unwrap();
+    let rule = |name: &str| CanonicalPrompt::parse(TemplateType::Rule, name, "Rule.\n").unwrap();
+    let mut manifest = SyncManifest::default();
+
+    let mut output = SyncOutput::new(temp.path());
+    CursorTarget
+      .render(&[rule("a.md"), rule("b.md"), rule("c.md")], &mut output)
+      .unwrap();
+    apply(&CursorTarget, &mut output, &mut manifest, false).unwrap();
+    std::fs::write(temp.path().join(".cursor/rules/c.mdc"), "Edited.\n").unwrap();
+
+    let mut output = SyncOutput::new(temp.path());
+    CursorTarget.render(&[rule("a.md")], &mut output).unwrap();
+    let changes = apply(&CursorTarget, &mut output, &mut manifest, false).unwrap();
+
+    assert_eq!(
+      changes.removed,
+      vec![temp.path().join(".cursor/rules/b.mdc")]
+    );
+    assert!(!temp.path().join(".cursor/rules/b.mdc").exists());
+    // An edited rule is kept
+    assert!(temp.path().join(".cursor/rules/c.mdc").exists());
+    assert_eq!(output.warnings().len(), 1);
+  }
+}
@@ -0,0 +1,143 @@
+//! Factory sync target
+//!
+//! | Prompt type | Output |
+//! |-------------|--------|
+//! | Command | `.factory/commands/<path>.md`, the path flattened with `_` |
+//! | Skill | `.factory/skills/<name>/SKILL.md` with its asset files |
+//!
+//! Droids read a few front matter keys only; the others are dropped with a
+//! warning naming each of them.
+
+use super::{CanonicalPrompt, SyncOutput, SyncTarget, flat_name};
+use crate::modules::config::TemplateType;
+use crate::modules::error::Result;
+use serde_norway::Mapping;
+use std::path::Path;
+
+/// Directory Factory reads commands from
+const COMMANDS_DIRECTORY: &str = ".factory/commands";
+
+/// Directory Factory reads skills from
+const SKILLS_DIRECTORY: &str = ".factory/skills";
+
+/// Front matter keys Factory reads from commands
+const COMMAND_KEYS: &[&str] = &["description", "argument-hint", "allowed-tools"];
+
+/// Front matter keys Factory reads from skills
+const SKILL_KEYS: &[&str] = &["name", "description"];
+
+/// Factory target
+#[derive(Debug, Clone, Copy, Default)]
+pub struct FactoryTarget;
+
+impl SyncTarget for FactoryTarget {
+  fn id(&self) -> &'static str {
+    "factory"
+  }
+
+  fn description(&self) -> &'static str {
+    "Factory: .factory/commands and .factory/skills"
+  }
+
+  fn render(&self, prompts: &[CanonicalPrompt], output: &mut SyncOutput) -> Result<()> {
+    for prompt in prompts {
+      match prompt.r#type {
+        TemplateType::Command => {
+          let front_matter = supported_front_matter(prompt, COMMAND_KEYS, output);
+          output.write(
+            Path::new(COMMANDS_DIRECTORY).join(flat_name(&prompt.path)),
+            prompt.render_with(&front_matter)?,
+          )?;
+        }
+        TemplateType::Skill => {
+          let front_matter = supported_front_matter(prompt, SKILL_KEYS, output);
+          let path = Path::new(SKILLS_DIRECTORY).join(&prompt.path);
+          let directory = path.parent().unwrap_or(Path::new(SKILLS_DIRECTORY));
+          for asset in &prompt.assets {
+            output.write(directory.join(asset), prompt.read_asset(asset)?)?;
+          }
+          output.write(path, prompt.render_with(&front_matter)?)?;
+        }
+        _ => {}
+      }
+    }
+    Ok(())
+  }
+
+  fn prunes(&self, path: &Path) -> bool {
+    path.starts_with(COMMANDS_DIRECTORY) || path.starts_with(SKILLS_DIRECTORY)
+  }
+}
+
+/// Front matter of a prompt restricted to the supported keys, warning about the
+/// others
+fn supported_front_matter(
+  prompt: &CanonicalPrompt,
+  keys: &[&str],
+  output: &mut SyncOutput,
+) -> Mapping {
+  let mut front_matter = Mapping::new();
+  for (key, value) in &prompt.front_matter {
+    match key.as_str() {
+      Some(name) if keys.contains(&name) => {
+        front_matter.insert(key.clone(), value.clone());
+      }
+      name => output.warn(format!(
+        "{}: front matter key '{}' is not supported by Factory and was dropped",
+        prompt.path.display(),
+        name
+          .map(str::to_string)
+          .unwrap_or_else(|| format!("{:?}", key))
+      )),
+    }
+  }
+  front_matter
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  #[test]
+  fn test_commands_and_skills_with_assets() {
+    let temp = tempfile::tempdir().unwrap();
+    std::fs::create_dir_all(temp.path().join("tsx/scripts")).unwrap();
+    std::fs::write(temp.path().join("tsx/scripts/check.sh"), "tsc\n").unwrap();
+    let mut skill = CanonicalPrompt::parse(
+      TemplateType::Skill,
+      "tsx/SKILL.md",
+      "---\nname: tsx\ndescription: Write TSX\n---\n\nUse hooks.\n",
+    )
+    .unwrap();
+    skill.source = temp.path().join("tsx/SKILL.md");
+    skill.assets = vec!["scripts/check.sh".into()];
+    let prompts = [
+      CanonicalPrompt::parse(
+        TemplateType::Command,
+        "pe/compile.md",
+        "---\ndescription: Compile\nexecution: turbo\nmodel: sonnet\n---\n\nCompile $1\n",
+      )
+      .unwrap(),
+      skill,
+    ];
+
+    let mut output = SyncOutput::new(temp.path());
+    FactoryTarget.render(&prompts, &mut output).unwrap();
+
+    assert_eq!(
+      output.file_str(".factory/commands/pe_compile.md"),
+      "---\ndescription: Compile\n---\n\nCompile $1\n"
+    );
+    assert_eq!(
+      output.file_str(".factory/skills/tsx/SKILL.md"),
+      "---\nname: tsx\ndescription: Write TSX\n---\n\nUse hooks.\n"
+    );
+    assert_eq!(
+      output.file_str(".factory/skills/tsx/scripts/check.sh"),
+      "tsc\n"
+    );
+    assert_eq!(output.warnings().len(), 2);
+    assert!(output.warnings()[0].contains("'execution'"));
+    assert!(output.warnings()[1].contains("'model'"));
+  }
+}
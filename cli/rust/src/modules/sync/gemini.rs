@@ -0,0 +1,145 @@
+//! Gemini CLI sync target
+//!
+//! | Prompt type | Output |
+//! |-------------|--------|
+//! | User and project memory | Marked sections of `GEMINI.md` |
+//! | Command | `.gemini/commands/<path>.toml` |
+//!
+//! Commands become TOML files with a `description` and a `prompt`, where
+//! `$ARGUMENTS` is translated to `{{args}}`. Gemini CLI passes the arguments as a
+//! whole, so positional placeholders such as `$1` are reported.
+
+use super::{CanonicalPrompt, SyncOutput, SyncTarget, merge_sections};
+use crate::modules::config::TemplateType;
+use crate::modules::error::{AgentError, Result};
+use regex::Regex;
+use serde::Serialize;
+use std::path::Path;
+use std::sync::LazyLock;
+
+/// Memory file Gemini CLI reads
+const MEMORY_FILE: &str = "GEMINI.md";
+
+/// Directory Gemini CLI reads commands from
+const COMMANDS_DIRECTORY: &str = ".gemini/commands";
+
+/// Positional argument placeholder, `$1` to `$9`
+static POSITIONAL: LazyLock<Regex> = LazyLock::new(|| Regex::new(r"\$[1-9]").unwrap());
+
+/// Custom command file of Gemini CLI
+#[derive(Debug, Serialize)]
+struct GeminiCommand {
+  #[serde(skip_serializing_if = "Option::is_none")]
+  description: Option<String>,
+  prompt: String,
+}
+
+/// Gemini CLI target
+#[derive(Debug, Clone, Copy, Default)]
+pub struct GeminiTarget;
+
+impl SyncTarget for GeminiTarget {
+  fn id(&self) -> &'static str {
+    "gemini"
+  }
+
+  fn description(&self) -> &'static str {
+    "Gemini CLI: GEMINI.md and .gemini/commands/*.toml"
+  }
+
+  fn render(&self, prompts: &[CanonicalPrompt], output: &mut SyncOutput) -> Result<()> {
+    let mut sections = Vec::new();
+    for prompt in prompts {
+      match prompt.r#type {
+        TemplateType::UserMemory | TemplateType::ProjectMemory => {
+          let kind = if prompt.r#type == TemplateType::UserMemory {
+            "user"
+          } else {
+            "project"
+          };
+          sections.push((
+            format!("{}/{}", kind, prompt.path.with_extension("").display()),
+            prompt.body.clone(),
+          ));
+        }
+        TemplateType::Command => {
+          if POSITIONAL.is_match(&prompt.body) {
+            output.warn(format!(
+              "{} uses positional arguments, Gemini CLI only passes them as a whole in {{{{args}}}}",
+              prompt.path.display()
+            ));
+          }
+          let command = GeminiCommand {
+            description: prompt.get_str("description").map(str::to_string),
+            prompt: prompt
+              .body
+              .trim_start_matches('\n')
+              .replace("$ARGUMENTS", "{{args}}"),
+          };
+          let content = toml::to_string(&command).map_err(|e| {
+            AgentError::Internal(format!("Failed to serialize Gemini command: {}", e))
+          })?;
+          output.write(
+            Path::new(COMMANDS_DIRECTORY).join(prompt.path.with_extension("toml")),
+            content,
+          )?;
+        }
+        _ => {}
+      }
+    }
+
+    let existing = output.existing(MEMORY_FILE);
+    if !sections.is_empty() || existing.is_some() {
+      output.write(MEMORY_FILE, merge_sections(existing.as_deref(), &sections))?;
+    }
+    Ok(())
+  }
+
+  fn prunes(&self, path: &Path) -> bool {
+    path.starts_with(COMMANDS_DIRECTORY) && path.extension().is_some_and(|e| e == "toml")
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  #[test]
+  fn test_memory_and_toml_commands() {
+    let temp = tempfile::tempdir().unwrap();
+    let prompts = [
+      CanonicalPrompt::parse(TemplateType::ProjectMemory, "root.md", "Guide.\n").unwrap(),
+      CanonicalPrompt::parse(
+        TemplateType::Command,
+        "git/commit.md",
+        "---\ndescription: Commit \"staged\" changes\n---\n\nCommit with message $ARGUMENTS.\n",
+      )
+      .unwrap(),
+      CanonicalPrompt::parse(TemplateType::Command, "diff.md", "Diff $1\n").unwrap(),
+    ];
+
+    let mut output = SyncOutput::new(temp.path());
+    GeminiTarget.render(&prompts, &mut output).unwrap();
+
+    assert_eq!(
+      output.file_str("GEMINI.md"),
+      "<!-- agents:begin project/root -->\nGuide.\n<!-- agents:end project/root -->\n"
+    );
+    let command: toml::Table =
+      toml::from_str(&output.file_str(".gemini/commands/git/commit.toml")).unwrap();
+    assert_eq!(
+      command["description"].as_str(),
+      Some("Commit \"staged\" changes")
+    );
+    assert_eq!(
+      command["prompt"].as_str(),
+      Some("Commit with message {{args}}.\n")
+    );
+    assert!(
+      !output
+        .file_str(".gemini/commands/diff.toml")
+        .contains("description")
+    );
+    assert_eq!(output.warnings().len(), 1);
+  }
+}
@@ -0,0 +1,154 @@
+//! Kiro sync target
+//!
+//! Project memory and rule prompts become steering files in `.kiro/steering/`,
+//! their scope translated to Kiro's inclusion modes:
+//!
+//! | Scope | Front matter |
+//! |-------|--------------|
+//! | `always` | `inclusion: always` |
+//! | `glob` | `inclusion: fileMatch` with `fileMatchPattern` |
+//! | `manual` | `inclusion: manual` |
+//! | `model-decision` | `inclusion: manual`, with a warning |
+//!
+//! Kiro matches a single pattern, so several globs are joined into one brace
+//! pattern. Steering files whose prompt was removed are removed on the next sync.
+
+use super::{CanonicalPrompt, Scope, SyncOutput, SyncTarget};
+use crate::modules::config::TemplateType;
+use crate::modules::error::Result;
+use serde_norway::Mapping;
+use std::path::Path;
+
+/// Directory Kiro reads steering files from
+const STEERING_DIRECTORY: &str = ".kiro/steering";
+
+/// Kiro target
+#[derive(Debug, Clone, Copy, Default)]
+pub struct KiroTarget;
+
+impl SyncTarget for KiroTarget {
+  fn id(&self) -> &'static str {
+    "kiro"
+  }
+
+  fn description(&self) -> &'static str {
+    "Kiro: .kiro/steering with inclusion modes"
+  }
+
+  fn render(&self, prompts: &[CanonicalPrompt], output: &mut SyncOutput) -> Result<()> {
+    for prompt in prompts {
+      if !matches!(
+        prompt.r#type,
+        TemplateType::ProjectMemory | TemplateType::Rule
+      ) {
+        continue;
+      }
+      let scope = match prompt.scope() {
+        Ok(scope) => scope,
+        Err(error) => {
+          output.skip(prompt, error.to_string());
+          continue;
+        }
+      };
+
+      let mut front_matter = Mapping::new();
+      match scope {
+        Scope::Always => {
+          front_matter.insert("inclusion".into(), "always".into());
+        }
+        Scope::Glob(globs) => {
+          let pattern = match globs.as_slice() {
+            [glob] => glob.clone(),
+            globs => format!("{{{}}}", globs.join(",")),
+          };
+          front_matter.insert("inclusion".into(), "fileMatch".into());
+          front_matter.insert("fileMatchPattern".into(), pattern.into());
+        }
+        Scope::Manual => {
+          front_matter.insert("inclusion".into(), "manual".into());
+        }
+        Scope::ModelDecision => {
+          output.warn(format!(
+            "{} has the model-decision scope, which Kiro lacks; it is included manually",
+            prompt.path.display()
+          ));
+          front_matter.insert("inclusion".into(), "manual".into());
+        }
+      }
+
+      output.write(
+        Path::new(STEERING_DIRECTORY).join(&prompt.path),
+        prompt.render_with(&front_matter)?,
+      )?;
+    }
+    Ok(())
+  }
+
+  fn prunes(&self, path: &Path) -> bool {
+    path.parent() == Some(Path::new(STEERING_DIRECTORY))
+      && path.extension().is_some_and(|e| e == "md")
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  #[test]
+  fn test_scopes_map_to_inclusion_modes() {
+    let prompts = [
+      CanonicalPrompt::parse(TemplateType::ProjectMemory, "_root.md", "Guide.\n").unwrap(),
+      CanonicalPrompt::parse(
+        TemplateType::Rule,
+        "_cli.md",
+        "---\nglobs: cli/**/*\n---\n\nUse cargo.\n",
+      )
+      .unwrap(),
+      CanonicalPrompt::parse(
+        TemplateType::Rule,
+        "_web.md",
+        "---\nscope: glob\nglobs: [web/**/*.ts, web/**/*.tsx]\n---\n\nUse pnpm.\n",
+      )
+      .unwrap(),
+      CanonicalPrompt::parse(
+        TemplateType::Rule,
+        "_review.md",
+        "---\nscope: model-decision\ndescription: Review rules\n---\n\nReview.\n",
+      )
+      .unwrap(),
+      CanonicalPrompt::parse(
+        TemplateType::Rule,
+        "_bad.md",
+        "---\nscope: sometimes\n---\n",
+      )
+      .unwrap(),
+    ];
+
+    let temp = tempfile::tempdir().unwrap();
+    let mut output = SyncOutput::new(temp.path());
+    KiroTarget.render(&prompts, &mut output).unwrap();
+
+    assert_eq!(
+      output.file_str(".kiro/steering/_root.md"),
+      "---\ninclusion: always\n---\nGuide.\n"
+    );
+    assert_eq!(
+      output.file_str(".kiro/steering/_cli.md"),
+      "---\ninclusion: fileMatch\nfileMatchPattern: cli/**/*\n---\n\nUse cargo.\n"
+    );
+    assert!(
+      output
+        .file_str(".kiro/steering/_web.md")
+        .contains("fileMatchPattern: '{web/**/*.ts,web/**/*.tsx}'\n")
+    );
+    assert!(
+      output
+        .file_str(".kiro/steering/_review.md")
+        .starts_with("---\ninclusion: manual\n---\n")
+    );
+    assert_eq!(output.files().len(), 4);
+    assert_eq!(output.warnings().len(), 1);
+    assert_eq!(output.skipped()[0].0, "rule/_bad.md");
+    assert!(output.skipped()[0].1.contains("unknown scope 'sometimes'"));
+  }
+}
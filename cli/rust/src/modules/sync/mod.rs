@@ -0,0 +1,866 @@
+//! Prompt distribution for the agents core system
+//!
+//! The `__ai/` directories hold the canonical prompts of every [`TemplateType`].
+//! A [`SyncTarget`] maps them to the files one AI tool reads, in its path layout
+//! and front matter dialect. Sync renders the enabled targets and writes only the
+//! files whose content changed, recording the files of each target and their
+//! hashes in a manifest.
+
+pub mod claude;
+pub mod codex;
+pub mod copilot;
+pub mod cursor;
+pub mod factory;
+pub mod gemini;
+pub mod kiro;
+pub mod qoder;
+pub mod windsurf;
+
+use super::cache::TemplateCache;
+use super::compile;
+use super::config::{DirectoryMappings, SyncSettings, TargetSettings, TemplateType};
+use super::error::{AgentError, Result};
+use super::frontmatter;
+use super::skill::{self, SKILL_ENTRY};
+use super::storage;
+use regex::Regex;
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use std::fmt;
+use std::path::{Component, Path, PathBuf};
+
+/// Opening marker of a generated section, followed by its id and ` -->`
+const SECTION_BEGIN: &str = "<!-- agents:begin ";
+
+/// Closing marker of a generated section, followed by its id and ` -->`
+const SECTION_END: &str = "<!-- agents:end ";
+
+/// Prompt types in the order prompts are collected
+const PROMPT_TYPES: [TemplateType; 7] = [
+  TemplateType::UserMemory,
+  TemplateType::ProjectMemory,
+  TemplateType::LocaleMemory,
+  TemplateType::SubAgent,
+  TemplateType::Command,
+  TemplateType::Skill,
+  TemplateType::Rule,
+];
+
+/// When a tool brings a prompt into context, from the `scope` front matter key
+///
+/// Without a `scope` key, prompts with `globs` are scoped to them and other
+/// prompts are always applied.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Scope {
+  /// Always in context
+  Always,
+  /// In context when working on files matching the globs
+  Glob(Vec<String>),
+  /// Only when referenced explicitly
+  Manual,
+  /// When the model judges the prompt relevant from its description
+  ModelDecision,
+}
+
+impl fmt::Display for Scope {
+  fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+    match self {
+      Scope::Always => write!(f, "always"),
+      Scope::Glob(_) => write!(f, "glob"),
+      Scope::Manual => write!(f, "manual"),
+      Scope::ModelDecision => write!(f, "model-decision"),
+    }
+  }
+}
+
+/// How freely a tool runs the steps of a command, from the `execution` front
+/// matter key
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ExecutionPolicy {
+  /// Every step is confirmed by the user
+  Manual,
+  /// Steps the tool judges safe run without confirmation
+  Auto,
+  /// Every step runs without confirmation
+  Turbo,
+}
+
+impl fmt::Display for ExecutionPolicy {
+  fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+    match self {
+      ExecutionPolicy::Manual => write!(f, "manual"),
+      ExecutionPolicy::Auto => write!(f, "auto"),
+      ExecutionPolicy::Turbo => write!(f, "turbo"),
+    }
+  }
+}
+
+/// Canonical prompt read from the `__ai/` directories
+#[derive(Debug, Clone, PartialEq)]
+pub struct CanonicalPrompt {
+  /// Prompt type, from the directory the prompt was found in
+  pub r#type: TemplateType,
+  /// Prompt name, the file stem or the skill directory name
+  pub name: String,
+  /// Path relative to the directory of the type
+  pub path: PathBuf,
+  /// File the prompt was read from
+  pub source: PathBuf,
+  /// Front matter of the prompt, empty when it has none
+  pub front_matter: serde_norway::Mapping,
+  /// Body after the front matter
+  pub body: String,
+  /// Asset files of a skill, relative to its directory
+  pub assets: Vec<PathBuf>,
+}
+
+impl CanonicalPrompt {
+  /// Parse a prompt document found at a path relative to the directory of its type
+  pub fn parse<P: AsRef<Path>>(r#type: TemplateType, path: P, content: &str) -> Result<Self> {
+    let path = path.as_ref();
+    let (front_matter, body) = match frontmatter::split(content) {
+      Some(_) => frontmatter::parse::<serde_norway::Mapping>(content)?,
+      None => (serde_norway::Mapping::new(), content.to_string()),
+    };
+    Ok(Self {
+      r#type,
+      name: path
+        .file_stem()
+        .and_then(|s| s.to_str())
+        .unwrap_or_default()
+        .to_string(),
+      path: path.to_path_buf(),
+      source: path.to_path_buf(),
+      front_matter,
+      body,
+      assets: Vec::new(),
+    })
+  }
+
+  /// Get a front matter value
+  pub fn get(&self, key: &str) -> Option<&serde_norway::Value> {
+    self.front_matter.get(key)
+  }
+
+  /// Get a front matter string value
+  pub fn get_str(&self, key: &str) -> Option<&str> {
+    self.get(key).and_then(|value| value.as_str())
+  }
+
+  /// File globs the prompt is scoped to, from a `globs` list or comma separated string
+  pub fn globs(&self) -> Vec<String> {
+    match self.get("globs") {
+      Some(serde_norway::Value::Sequence(globs)) => globs
+        .iter()
+        .filter_map(|glob| glob.as_str())
+        .map(|glob| glob.trim().to_string())
+        .filter(|glob| !glob.is_empty())
+        .collect(),
+      Some(serde_norway::Value::String(globs)) => globs
+        .split(',')
+        .map(|glob| glob.trim().to_string())
+        .filter(|glob| !glob.is_empty())
+        .collect(),
+      _ => Vec::new(),
+    }
+  }
+
+  /// Scope of the prompt
+  pub fn scope(&self) -> Result<Scope> {
+    let globs = self.globs();
+    match self.get_str("scope") {
+      None if globs.is_empty() => Ok(Scope::Always),
+      None => Ok(Scope::Glob(globs)),
+      Some("always") => Ok(Scope::Always),
+      Some("glob") if globs.is_empty() => Err(AgentError::Template(format!(
+        "{} is scoped to globs but has no `globs`",
+        self.path.display()
+      ))),
+      Some("glob") => Ok(Scope::Glob(globs)),
+      Some("manual") => Ok(Scope::Manual),
+      Some("model-decision") => Ok(Scope::ModelDecision),
+      Some(scope) => Err(AgentError::Template(format!(
+        "{} has unknown scope '{}', expected always, glob, manual or model-decision",
+        self.path.display(),
+        scope
+      ))),
+    }
+  }
+
+  /// Identifier of the prompt, its type and path such as `rule/lang/rust.md`
+  pub fn id(&self) -> String {
+    let path: Vec<String> = self
+      .path
+      .iter()
+      .map(|component| component.to_string_lossy().to_string())
+      .collect();
+    format!("{}/{}", self.r#type, path.join("/"))
+  }
+
+  /// Execution policy of a command, `None` when it leaves it to the tool
+  pub fn execution(&self) -> Result<Option<ExecutionPolicy>> {
+    match self.get_str("execution") {
+      None => Ok(None),
+      Some("manual") => Ok(Some(ExecutionPolicy::Manual)),
+      Some("auto") => Ok(Some(ExecutionPolicy::Auto)),
+      Some("turbo") => Ok(Some(ExecutionPolicy::Turbo)),
+      Some(policy) => Err(AgentError::Template(format!(
+        "{} has unknown execution policy '{}', expected manual, auto or turbo",
+        self.path.display(),
+        policy
+      ))),
+    }
+  }
+
+  /// Read an asset file of a skill
+  pub fn read_asset(&self, asset: &Path) -> Result<Vec<u8>> {
+    let path = self.source.parent().unwrap_or(Path::new("")).join(asset);
+    std::fs::read(&path)
+      .map_err(|e| AgentError::Storage(format!("Failed to read {}: {}", path.display(), e)))
+  }
+
+  /// Render the prompt with other front matter, leaving it out when empty
+  pub fn render_with(&self, front_matter: &serde_norway::Mapping) -> Result<String> {
+    if front_matter.is_empty() {
+      Ok(self.body.trim_start_matches('\n').to_string())
+    } else {
+      frontmatter::render(front_matter, &self.body)
+    }
+  }
+}
+
+/// Read the canonical prompts of every type, sorted by type and path
+///
+/// Only compiled prompts are read; a `.src.md` source without its compiled
+/// sibling is reported as a warning.
+pub fn collect_prompts(
+  directories: &DirectoryMappings,
+  warnings: &mut Vec<String>,
+) -> Result<Vec<CanonicalPrompt>> {
+  let mut prompts = Vec::new();
+  for r#type in PROMPT_TYPES {
+    let directory = Path::new(directories.directory_for(&r#type));
+    if r#type == TemplateType::Skill {
+      for skill in skill::discover(directory)? {
+        let name = skill.name().to_string();
+        let path = skill
+          .directory
+          .strip_prefix(directory)
+          .unwrap_or(&skill.directory)
+          .join(SKILL_ENTRY);
+        let front_matter = serde_norway::from_value(serde_norway::to_value(&skill.front_matter)?)?;
+        prompts.push(CanonicalPrompt {
+          r#type: r#type.clone(),
+          name,
+          source: skill.directory.join(SKILL_ENTRY),
+          path,
+          front_matter,
+          body: skill.body,
+          assets: skill
+            .assets
+            .into_iter()
+            .filter(|asset| !compile::is_source_file(asset))
+            .collect(),
+        });
+      }
+      continue;
+    }
+
+    for entry in walkdir::WalkDir::new(directory)
+      .sort_by_file_name()
+      .into_iter()
+      .filter_entry(|entry| entry.depth() == 0 || !is_hidden(entry.path()))
+      .filter_map(|entry| entry.ok())
+      .filter(|entry| entry.file_type().is_file())
+    {
+      let source = entry.path();
+      if compile::is_source_file(source) {
+        if let Some(output) = compile::output_path(source)
+          && !output.exists()
+        {
+          warnings.push(format!(
+            "{} is not compiled; run `compile` to sync it",
+            source.display()
+          ));
+        }
+        continue;
+      }
+      if source.extension().and_then(|e| e.to_str()) != Some("md") {
+        continue;
+      }
+
+      let content = std::fs::read_to_string(source)
+        .map_err(|e| AgentError::Storage(format!("Failed to read {}: {}", source.display(), e)))?;
+      let path = source.strip_prefix(directory).unwrap_or(source);
+      let mut prompt = CanonicalPrompt::parse(r#type.clone(), path, &content)
+        .map_err(|e| AgentError::Template(format!("{}: {}", source.display(), e)))?;
+      prompt.source = source.to_path_buf();
+      prompts.push(prompt);
+    }
+  }
+  Ok(prompts)
+}
+
+/// File name for a prompt in a flat directory, the components of its path
+/// joined by `_`: `pe/compile.md` becomes `pe_compile.md`
+pub fn flat_name(path: &Path) -> PathBuf {
+  let components: Vec<String> = path
+    .iter()
+    .map(|component| component.to_string_lossy().to_string())
+    .collect();
+  PathBuf::from(components.join("_"))
+}
+
+fn is_hidden(path: &Path) -> bool {
+  path
+    .file_name()
+    .and_then(|s| s.to_str())
+    .is_some_and(|name| name.starts_with('.'))
+}
+
+/// Files and warnings produced by a target
+pub struct SyncOutput {
+  root: PathBuf,
+  settings: TargetSettings,
+  files: BTreeMap<PathBuf, Vec<u8>>,
+  warnings: Vec<String>,
+  skipped: Vec<(String, String)>,
+  previous: Vec<PathBuf>,
+}
+
+impl SyncOutput {
+  /// Create an output for files relative to the project root
+  pub fn new<P: AsRef<Path>>(root: P) -> Self {
+    Self {
+      root: root.as_ref().to_path_buf(),
+      settings: TargetSettings::default(),
+      files: BTreeMap::new(),
+      warnings: Vec::new(),
+      skipped: Vec::new(),
+      previous: Vec::new(),
+    }
+  }
+
+  /// Use the settings of the target
+  pub fn with_settings(mut self, settings: TargetSettings) -> Self {
+    self.settings = settings;
+    self
+  }
+
+  /// Remember the files the target produced on the previous sync
+  pub fn with_manifest(mut self, manifest: &SyncManifest, target: &str) -> Self {
+    self.previous = manifest
+      .targets
+      .get(target)
+      .map(|files| files.keys().map(PathBuf::from).collect())
+      .unwrap_or_default();
+    self
+  }
+
+  /// Get the files the target produced on the previous sync
+  pub fn previous(&self) -> &[PathBuf] {
+    &self.previous
+  }
+
+  /// Get the settings of the target
+  pub fn settings(&self) -> &TargetSettings {
+    &self.settings
+  }
+
+  /// Get the project root
+  pub fn root(&self) -> &Path {
+    &self.root
+  }
+
+  /// Add a file, relative to the project root
+  pub fn write<P: Into<PathBuf>, C: Into<Vec<u8>>>(&mut self, path: P, content: C) -> Result<()> {
+    let path = path.into();
+    if path.as_os_str().is_empty()
+      || path
+        .components()
+        .any(|c| !matches!(c, Component::Normal(_) | Component::CurDir))
+    {
+      return Err(AgentError::InvalidPath(format!(
+        "Sync target path '{}' must be relative to the project root",
+        path.display()
+      )));
+    }
+    if self.files.insert(path.clone(), content.into()).is_some() {
+      self.warn(format!(
+        "{} was produced twice, keeping the last",
+        path.display()
+      ));
+    }
+    Ok(())
+  }
+
+  /// Report a problem that does not stop the target
+  pub fn warn<M: Into<String>>(&mut self, message: M) {
+    self.warnings.push(message.into());
+  }
+
+  /// Report a prompt the target leaves out, with the reason
+  pub fn skip<R: Into<String>>(&mut self, prompt: &CanonicalPrompt, reason: R) {
+    self.skipped.push((prompt.id(), reason.into()));
+  }
+
+  /// Keep the prompts the `include` and `exclude` settings give the target,
+  /// reporting the others as skipped
+  pub fn select(&mut self, prompts: &[CanonicalPrompt]) -> Result<Vec<CanonicalPrompt>> {
+    let include = patterns(&self.settings.include)?;
+    let exclude = patterns(&self.settings.exclude)?;
+    let mut selected = Vec::new();
+    for prompt in prompts {
+      let id = prompt.id();
+      if !include.is_empty() && !include.iter().any(|(_, regex)| regex.is_match(&id)) {
+        self.skip(prompt, "not included");
+      } else if let Some((pattern, _)) = exclude.iter().find(|(_, regex)| regex.is_match(&id)) {
+        self.skip(prompt, format!("excluded by '{}'", pattern));
+      } else {
+        selected.push(prompt.clone());
+      }
+    }
+    Ok(selected)
+  }
+
+  /// Read the current content of a file, relative to the project root
+  pub fn existing<P: AsRef<Path>>(&self, path: P) -> Option<String> {
+    std::fs::read_to_string(self.root.join(path)).ok()
+  }
+
+  /// Get the files produced so far
+  pub fn files(&self) -> &BTreeMap<PathBuf, Vec<u8>> {
+    &self.files
+  }
+
+  /// Get a produced file as text, panicking when it was not produced
+  #[cfg(test)]
+  pub(crate) fn file_str(&self, path: &str) -> String {
+    String::from_utf8(self.files[Path::new(path)].clone()).unwrap()
+  }
+
+  /// Get the warnings reported so far
+  pub fn warnings(&self) -> &[String] {
+    &self.warnings
+  }
+
+  /// Get the identifiers of the prompts skipped so far, with the reasons
+  pub fn skipped(&self) -> &[(String, String)] {
+    &self.skipped
+  }
+}
+
+/// Compile prompt patterns, where `**` matches any path, `*` a path component
+/// part and `?` one character
+fn patterns(patterns: &[String]) -> Result<Vec<(String, Regex)>> {
+  patterns
+    .iter()
+    .map(|pattern| {
+      let mut expression = String::from("^");
+      let mut chars = pattern.chars().peekable();
+      while let Some(c) = chars.next() {
+        match c {
+          '*' if chars.peek() == Some(&'*') => {
+            chars.next();
+            expression.push_str(".*");
+          }
+          '*' => expression.push_str("[^/]*"),
+          '?' => expression.push_str("[^/]"),
+          c => expression.push_str(&regex::escape(&c.to_string())),
+        }
+      }
+      expression.push('$');
+      Regex::new(&expression)
+        .map(|regex| (pattern.clone(), regex))
+        .map_err(|e| {
+          AgentError::Configuration(format!("Invalid prompt pattern '{}': {}", pattern, e))
+        })
+    })
+    .collect()
+}
+
+/// Replace the generated sections of a document, keeping the hand-written rest
+///
+/// Every section is wrapped in `<!-- agents:begin id -->` and `<!-- agents:end id -->`
+/// markers. Sections already in the document are replaced in place, sections
+/// whose id is gone are dropped, and new ones follow the last existing section,
+/// or end the document when it has none. Text between sections is kept.
+pub fn merge_sections(existing: Option<&str>, sections: &[(String, String)]) -> String {
+  let render = |id: &str, content: &str| {
+    format!(
+      "{}{} -->\n{}\n{}{} -->",
+      SECTION_BEGIN,
+      id,
+      content.trim_matches('\n'),
+      SECTION_END,
+      id
+    )
+  };
+  let existing = existing.unwrap_or_default();
+  let blocks = section_blocks(existing);
+  let Some(last) = blocks.last().map(|(id, _, _)| id.clone()) else {
+    if sections.is_empty() {
+      return existing.to_string();
+    }
+    let generated = sections
+      .iter()
+      .map(|(id, content)| render(id, content))
+      .collect::<Vec<_>>()
+      .join("\n\n");
+    return if existing.trim().is_empty() {
+      format!("{}\n", generated)
+    } else {
+      format!("{}\n\n{}\n", existing.trim_end(), generated)
+    };
+  };
+
+  let mut merged = String::new();
+  let mut position = 0;
+  for (id, start, end) in &blocks {
+    let mut text = &existing[position..*start];
+    if merged.is_empty() || merged.ends_with("\n\n") {
+      // Drop the blank lines left by a removed section
+      text = text.trim_start_matches('\n');
+    }
+    merged.push_str(text);
+
+    let mut generated: Vec<String> = sections
+      .iter()
+      .filter(|(section, _)| section == id)
+      .map(|(section, content)| render(section, content))
+      .collect();
+    if *id == last {
+      generated.extend(
+        sections
+          .iter()
+          .filter(|(section, _)| !blocks.iter().any(|(block, _, _)| block == section))
+          .map(|(section, content)| render(section, content)),
+      );
+    }
+    merged.push_str(&generated.join("\n\n"));
+    position = *end;
+  }
+  let mut rest = &existing[position..];
+  if merged.is_empty() || merged.ends_with("\n\n") {
+    rest = rest.trim_start_matches('\n');
+  }
+  merged.push_str(rest);
+  if rest.trim().is_empty() && !merged.trim().is_empty() {
+    // Drop the blank lines left by a removed last section
+    merged.truncate(merged.trim_end().len());
+    merged.push('\n');
+  }
+  merged
+}
+
+/// Generated sections of a document, as their ids with their start and end offsets
+fn section_blocks(document: &str) -> Vec<(String, usize, usize)> {
+  let mut blocks = Vec::new();
+  let mut position = 0;
+  while let Some(offset) = document[position..].find(SECTION_BEGIN) {
+    let start = position + offset;
+    let id_start = start + SECTION_BEGIN.len();
+    let Some(id_end) = document[id_start..].find(" -->").map(|end| id_start + end) else {
+      break;
+    };
+    let id = &document[id_start..id_end];
+    let closing = format!("{}{} -->", SECTION_END, id);
+    match document[id_end..].find(&closing) {
+      Some(end) => {
+        let end = id_end + end + closing.len();
+        blocks.push((id.to_string(), start, end));
+        position = end;
+      }
+      None => position = id_end,
+    }
+  }
+  blocks
+}
+
+/// Distribution of the canonical prompts to one AI tool
+pub trait SyncTarget {
+  /// Identifier of the target in the configuration
+  fn id(&self) -> &'static str;
+
+  /// Short description of the tool and the files the target writes
+  fn description(&self) -> &'static str;
+
+  /// Produce the files of the target for the canonical prompts
+  fn render(&self, prompts: &[CanonicalPrompt], output: &mut SyncOutput) -> Result<()>;
+
+  /// Check whether a file the target wrote before is removed once no prompt
+  /// produces it anymore
+  fn prunes(&self, _path: &Path) -> bool {
+    false
+  }
+}
+
+/// Registry of the available sync targets
+#[derive(Default)]
+pub struct SyncRegistry {
+  targets: BTreeMap<&'static str, Box<dyn SyncTarget>>,
+}
+
+impl SyncRegistry {
+  /// Create a registry of the built-in targets
+  pub fn new() -> Self {
+    let mut registry = Self::default();
+    registry.register(Box::new(claude::ClaudeTarget));
+    registry.register(Box::new(codex::CodexTarget));
+    registry.register(Box::new(copilot::CopilotTarget));
+    registry.register(Box::new(cursor::CursorTarget));
+    registry.register(Box::new(factory::FactoryTarget));
+    registry.register(Box::new(gemini::GeminiTarget));
+    registry.register(Box::new(kiro::KiroTarget));
+    registry.register(Box::new(qoder::RuleTarget::qoder()));
+    registry.register(Box::new(qoder::RuleTarget::codebuddy()));
+    registry.register(Box::new(windsurf::WorkflowTarget::windsurf()));
+    registry.register(Box::new(windsurf::WorkflowTarget::antigravity()));
+    registry
+  }
+
+  /// Register a target, replacing a target with the same identifier
+  pub fn register(&mut self, target: Box<dyn SyncTarget>) {
+    self.targets.insert(target.id(), target);
+  }
+
+  /// Get a target by identifier
+  pub fn get(&self, id: &str) -> Option<&dyn SyncTarget> {
+    self.targets.get(id).map(|target| target.as_ref())
+  }
+
+  /// List the targets, ordered by identifier
+  pub fn targets(&self) -> impl Iterator<Item = &dyn SyncTarget> {
+    self.targets.values().map(|target| target.as_ref())
+  }
+
+  /// Resolve target identifiers, failing on unknown ones
+  pub fn resolve(&self, ids: &[String]) -> Result<Vec<&dyn SyncTarget>> {
+    ids
+      .iter()
+      .map(|id| {
+        self.get(id).ok_or_else(|| {
+          AgentError::Configuration(format!(
+            "Unknown sync target '{}', available: {}",
+            id,
+            self.targets.keys().copied().collect::<Vec<_>>().join(", ")
+          ))
+        })
+      })
+      .collect()
+  }
+
+  /// Resolve the targets enabled in the settings
+  pub fn enabled(&self, settings: &SyncSettings) -> Result<Vec<&dyn SyncTarget>> {
+    self.resolve(&settings.targets)
+  }
+}
+
+/// Files written by every target and their hashes, by target and path
+#[derive(Debug, Clone, Default, Serialize, Deserialize, PartialEq, Eq)]
+pub struct SyncManifest {
+  pub targets: BTreeMap<String, BTreeMap<String, String>>,
+}
+
+impl SyncManifest {
+  /// Load a manifest, starting empty when it is missing or corrupt
+  pub fn load<P: AsRef<Path>>(path: P) -> Result<Self> {
+    let path = path.as_ref();
+    if !path.exists() {
+      return Ok(Self::default());
+    }
+    let content = std::fs::read_to_string(path)
+      .map_err(|e| AgentError::Storage(format!("Failed to read sync manifest: {}", e)))?;
+    Ok(serde_json::from_str(&content).unwrap_or_else(|e| {
+      tracing::warn!("Discarding corrupt sync manifest: {}", e);
+      Self::default()
+    }))
+  }
+
+  /// Save the manifest atomically
+  pub fn save<P: AsRef<Path>>(&self, path: P) -> Result<()> {
+    let content = serde_json::to_string_pretty(self)
+      .map_err(|e| AgentError::Internal(format!("Failed to serialize sync manifest: {}", e)))?;
+    storage::write_atomic(path, content)
+  }
+}
+
+/// Files changed by applying the output of a target
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct SyncChanges {
+  /// Files written because their content changed
+  pub written: Vec<PathBuf>,
+  /// Stale files removed
+  pub removed: Vec<PathBuf>,
+}
+
+/// Write the files of a target whose content changed, and remove the files it
+/// wrote before that no prompt produces anymore, when the target prunes them
+///
+/// A stale file edited since it was written is kept with a warning. With
+/// `dry_run`, nothing is written or removed and the manifest is left unchanged.
+pub fn apply(
+  target: &dyn SyncTarget,
+  output: &mut SyncOutput,
+  manifest: &mut SyncManifest,
+  dry_run: bool,
+) -> Result<SyncChanges> {
+  let mut changes = SyncChanges::default();
+  let mut hashes = BTreeMap::new();
+  for (path, content) in output.files() {
+    let full_path = output.root().join(path);
+    if std::fs::read(&full_path).ok().as_deref() != Some(content.as_slice()) {
+      if !dry_run {
+        storage::write_atomic(&full_path, content)?;
+      }
+      changes.written.push(full_path);
+    }
+    hashes.insert(
+      path.to_string_lossy().to_string(),
+      TemplateCache::hash(content),
+    );
+  }
+
+  let previous = manifest
+    .targets
+    .get(target.id())
+    .cloned()
+    .unwrap_or_default();
+  for (path, hash) in previous {
+    if hashes.contains_key(&path) || !target.prunes(Path::new(&path)) {
+      continue;
+    }
+    let full_path = output.root().join(&path);
+    match std::fs::read(&full_path) {
+      Ok(content) if TemplateCache::hash(&content) == hash => {
+        if !dry_run {
+          std::fs::remove_file(&full_path).map_err(|e| {
+            AgentError::Storage(format!("Failed to remove {}: {}", full_path.display(), e))
+          })?;
+        }
+        changes.removed.push(full_path);
+      }
+      Ok(_) => output.warn(format!(
+        "{} has no source prompt anymore but was edited, keeping it",
+        path
+      )),
+      Err(_) => {}
+    }
+  }
+
+  if !dry_run {
+    manifest.targets.insert(target.id().to_string(), hashes);
+  }
+  Ok(changes)
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  /// Target copying commands with their description only
+  struct CommandCopy;
+
+  impl SyncTarget for CommandCopy {
+    fn id(&self) -> &'static str {
+      "copy"
+    }
+
+    fn description(&self) -> &'static str {
+      "Copies commands"
+    }
+
+    fn render(&self, prompts: &[CanonicalPrompt], output: &mut SyncOutput) -> Result<()> {
+      for prompt in prompts {
+        if prompt.r#type != TemplateType::Command {
+          continue;
+        }
+        let mut front_matter = serde_norway::Mapping::new();
+        if let Some(description) = prompt.get("description") {
+          front_matter.insert("description".into(), description.clone());
+        }
+        output.write(
+          Path::new(".copy").join(&prompt.path),
+          prompt.render_with(&front_matter)?,
+        )?;
+      }
+      Ok(())
+    }
+  }
+
+  #[test]
+  fn test_merge_sections_keeps_text_between_sections() {
+    let existing = "# Guide\n\n<!-- agents:begin a -->\nold a\n<!-- agents:end a -->\n\nNotes on a.\n\n<!-- agents:begin gone -->\nold\n<!-- agents:end gone -->\n\n<!-- agents:begin b -->\nold b\n<!-- agents:end b -->\n\nFooter\n";
+    let sections = [
+      ("a".to_string(), "new a".to_string()),
+      ("b".to_string(), "new b".to_string()),
+      ("c".to_string(), "new c".to_string()),
+    ];
+
+    assert_eq!(
+      merge_sections(Some(existing), &sections),
+      "# Guide\n\n<!-- agents:begin a -->\nnew a\n<!-- agents:end a -->\n\nNotes on a.\n\n<!-- agents:begin b -->\nnew b\n<!-- agents:end b -->\n\n<!-- agents:begin c -->\nnew c\n<!-- agents:end c -->\n\nFooter\n"
+    );
+  }
+
+  #[test]
+  fn test_sync_writes_only_changed_files() {
+    let temp = tempfile::tempdir().unwrap();
+    let root = temp.path();
+    let mut directories = DirectoryMappings::default();
+    for directory in [
+      &mut directories.memory,
+      &mut directories.user,
+      &mut directories.project,
+      &mut directories.sub_agent,
+      &mut directories.command,
+      &mut directories.skill,
+      &mut directories.rule,
+    ] {
+      *directory = root.join(&*directory).to_string_lossy().to_string();
+    }
+    let commands = root.join("__ai/cmd");
+    std::fs::create_dir_all(commands.join("pe")).unwrap();
+    std::fs::write(
+      commands.join("pe/compile.md"),
+      "---\ndescription: Compile\nauto_execution_mode: 3\n---\n\nCompile sources\n",
+    )
+    .unwrap();
+    std::fs::write(commands.join("draft.src.md"), "Draft\n").unwrap();
+
+    let mut warnings = Vec::new();
+    let prompts = collect_prompts(&directories, &mut warnings).unwrap();
+    assert_eq!(prompts.len(), 1);
+    assert_eq!(prompts[0].name, "compile");
+    assert!(warnings[0].contains("draft.src.md is not compiled"));
+
+    let mut registry = SyncRegistry::new();
+    registry.register(Box::new(CommandCopy));
+    let settings = SyncSettings {
+      targets: vec!["copy".to_string()],
+      ..Default::default()
+    };
+    let target = registry.enabled(&settings).unwrap()[0];
+    let mut output = SyncOutput::new(root);
+    target.render(&prompts, &mut output).unwrap();
+
+    let mut manifest = SyncManifest::default();
+    let changes = apply(target, &mut output, &mut manifest, true).unwrap();
+    assert_eq!(changes.written.len(), 1);
+    assert!(!root.join(".copy/pe/compile.md").exists());
+    let changes = apply(target, &mut output, &mut manifest, false).unwrap();
+    assert_eq!(changes.written.len(), 1);
+    assert_eq!(
+      std::fs::read_to_string(root.join(".copy/pe/compile.md")).unwrap(),
+      "---\ndescription: Compile\n---\n\nCompile sources\n"
+    );
+    assert!(manifest.targets["copy"].contains_key(".copy/pe/compile.md"));
+    assert_eq!(
+      apply(target, &mut output, &mut manifest, false).unwrap(),
+      SyncChanges::default()
+    );
+
+    let unknown = registry.resolve(&["nope".to_string()]);
+    assert!(unknown.is_err());
+  }
+}
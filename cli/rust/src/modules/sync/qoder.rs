@@ -0,0 +1,176 @@
+//! Qoder and CodeBuddy sync targets
+//!
+//! Both tools read rules with a `trigger` from a flat directory, `.qoder/rules/`
+//! as `.md` files and `.codebuddy/.rules/` as `.mdc` files. Project memory and
+//! rule prompts become rules named after their path, with the scope translated
+//! to the trigger:
+//!
+//! | Scope | Front matter |
+//! |-------|--------------|
+//! | `always` | `trigger: always_on` |
+//! | `glob` | `trigger: glob` with `glob` |
+//! | `manual` | `trigger: manual` |
+//! | `model-decision` | `trigger: model_decision` with `description` |
+
+use super::{CanonicalPrompt, Scope, SyncOutput, SyncTarget, flat_name};
+use crate::modules::config::TemplateType;
+use crate::modules::error::Result;
+use std::path::Path;
+
+/// Rules target of Qoder or CodeBuddy
+#[derive(Debug, Clone, Copy)]
+pub struct RuleTarget {
+  id: &'static str,
+  description: &'static str,
+  directory: &'static str,
+  extension: &'static str,
+}
+
+impl RuleTarget {
+  /// Qoder target
+  pub fn qoder() -> Self {
+    Self {
+      id: "qoder",
+      description: "Qoder: .qoder/rules with triggers",
+      directory: ".qoder/rules",
+      extension: "md",
+    }
+  }
+
+  /// CodeBuddy target
+  pub fn codebuddy() -> Self {
+    Self {
+      id: "codebuddy",
+      description: "CodeBuddy: .codebuddy/.rules with triggers",
+      directory: ".codebuddy/.rules",
+      extension: "mdc",
+    }
+  }
+}
+
+impl SyncTarget for RuleTarget {
+  fn id(&self) -> &'static str {
+    self.id
+  }
+
+  fn description(&self) -> &'static str {
+    self.description
+  }
+
+  fn render(&self, prompts: &[CanonicalPrompt], output: &mut SyncOutput) -> Result<()> {
+    for prompt in prompts {
+      if !matches!(
+        prompt.r#type,
+        TemplateType::ProjectMemory | TemplateType::Rule
+      ) {
+        continue;
+      }
+      let scope = match prompt.scope() {
+        Ok(scope) => scope,
+        Err(error) => {
+          output.skip(prompt, error.to_string());
+          continue;
+        }
+      };
+
+      // Written by hand since the tools read globs such as `**` unquoted
+      let trigger = match scope {
+        Scope::Always => "trigger: always_on\n".to_string(),
+        Scope::Glob(globs) => format!("trigger: glob\nglob: {}\n", globs.join(",")),
+        Scope::Manual => "trigger: manual\n".to_string(),
+        Scope::ModelDecision => match prompt.get("description") {
+          Some(description) => format!(
+            "trigger: model_decision\ndescription: {}",
+            serde_norway::to_string(description)?
+          ),
+          None => {
+            output.skip(prompt, "the model-decision scope needs a description");
+            continue;
+          }
+        },
+      };
+
+      let path =
+        Path::new(self.directory).join(flat_name(&prompt.path.with_extension(self.extension)));
+      output.write(path, format!("---\n{}---\n{}", trigger, prompt.body))?;
+    }
+    Ok(())
+  }
+
+  fn prunes(&self, path: &Path) -> bool {
+    path.parent() == Some(Path::new(self.directory))
+      && path.extension().is_some_and(|e| e == self.extension)
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+  use crate::modules::config::TargetSettings;
+
+  #[test]
+  fn test_triggers_and_prompt_selection() {
+    let prompts = [
+      CanonicalPrompt::parse(TemplateType::ProjectMemory, "_project.md", "Guide.\n").unwrap(),
+      CanonicalPrompt::parse(
+        TemplateType::Rule,
+        "_cli.md",
+        "---\nglobs: cli/**/*\n---\n\n# CLI\n",
+      )
+      .unwrap(),
+      CanonicalPrompt::parse(
+        TemplateType::Rule,
+        "_review.md",
+        "---\nscope: model-decision\n---\n\nReview.\n",
+      )
+      .unwrap(),
+      CanonicalPrompt::parse(TemplateType::Rule, "draft/_web.md", "Web.\n").unwrap(),
+    ];
+
+    let temp = tempfile::tempdir().unwrap();
+    let mut output = SyncOutput::new(temp.path()).with_settings(TargetSettings {
+      include: vec!["rule/**".to_string()],
+      exclude: vec!["rule/draft/*".to_string()],
+      ..Default::default()
+    });
+    let selected = output.select(&prompts).unwrap();
+    RuleTarget::codebuddy()
+      .render(&selected, &mut output)
+      .unwrap();
+
+    assert_eq!(
+      output.file_str(".codebuddy/.rules/_cli.mdc"),
+      "---\ntrigger: glob\nglob: cli/**/*\n---\n\n# CLI\n"
+    );
+    assert_eq!(output.files().len(), 1);
+    assert_eq!(
+      output.skipped(),
+      [
+        (
+          "project-memory/_project.md".to_string(),
+          "not included".to_string()
+        ),
+        (
+          "rule/draft/_web.md".to_string(),
+          "excluded by 'rule/draft/*'".to_string()
+        ),
+        (
+          "rule/_review.md".to_string(),
+          "the model-decision scope needs a description".to_string()
+        ),
+      ]
+    );
+
+    let mut output = SyncOutput::new(temp.path());
+    RuleTarget::qoder().render(&prompts, &mut output).unwrap();
+    assert_eq!(
+      output.file_str(".qoder/rules/_project.md"),
+      "---\ntrigger: always_on\n---\nGuide.\n"
+    );
+    assert!(
+      output
+        .files()
+        .contains_key(Path::new(".qoder/rules/draft__web.md"))
+    );
+  }
+}
@@ -0,0 +1,176 @@
+//! Windsurf and Antigravity sync targets
+//!
+//! Both tools read workflows from a flat directory, `.windsurf/workflows/` and
+//! `.agent/workflows/`. Command prompts become workflows named after their path,
+//! `pe/compile.md` becoming `pe_compile.md`, with their front matter kept and the
+//! `execution` policy translated to `auto_execution_mode`:
+//!
+//! | Policy | `auto_execution_mode` |
+//! |--------|-----------------------|
+//! | `manual` | 1 |
+//! | `auto` | 2 |
+//! | `turbo` | 3 |
+//!
+//! Each tool limits the size of a workflow; the target fails listing every
+//! workflow over the limit, or over the `max_size` setting when set.
+
+use super::{CanonicalPrompt, ExecutionPolicy, SyncOutput, SyncTarget, flat_name};
+use crate::modules::config::TemplateType;
+use crate::modules::error::{AgentError, Result};
+use serde_norway::Value;
+use std::path::Path;
+
+/// Front matter key the tools read the execution mode from
+const EXECUTION_MODE_KEY: &str = "auto_execution_mode";
+
+/// Workflow target of Windsurf or Antigravity
+#[derive(Debug, Clone, Copy)]
+pub struct WorkflowTarget {
+  id: &'static str,
+  description: &'static str,
+  directory: &'static str,
+  max_size: usize,
+}
+
+impl WorkflowTarget {
+  /// Windsurf target, workflows of at most 12000 characters
+  ///
+  /// See <https://docs.windsurf.com/windsurf/cascade/workflows>.
+  pub fn windsurf() -> Self {
+    Self {
+      id: "windsurf",
+      description: "Windsurf: .windsurf/workflows from commands",
+      directory: ".windsurf/workflows",
+      max_size: 12_000,
+    }
+  }
+
+  /// Antigravity target, workflows of at most 12000 characters
+  ///
+  /// See <https://antigravity.google/docs/rules-workflows>.
+  pub fn antigravity() -> Self {
+    Self {
+      id: "antigravity",
+      description: "Antigravity: .agent/workflows from commands",
+      directory: ".agent/workflows",
+      max_size: 12_000,
+    }
+  }
+}
+
+impl SyncTarget for WorkflowTarget {
+  fn id(&self) -> &'static str {
+    self.id
+  }
+
+  fn description(&self) -> &'static str {
+    self.description
+  }
+
+  fn render(&self, prompts: &[CanonicalPrompt], output: &mut SyncOutput) -> Result<()> {
+    let max_size = output.settings().max_size.unwrap_or(self.max_size);
+    let mut oversized = Vec::new();
+    for prompt in prompts {
+      if prompt.r#type != TemplateType::Command {
+        continue;
+      }
+      let execution = match prompt.execution() {
+        Ok(execution) => execution,
+        Err(error) => {
+          output.skip(prompt, error.to_string());
+          continue;
+        }
+      };
+
+      let mut front_matter = prompt.front_matter.clone();
+      front_matter.remove("execution");
+      if let Some(execution) = execution {
+        front_matter.insert(EXECUTION_MODE_KEY.into(), execution_mode(execution));
+      }
+      let content = prompt.render_with(&front_matter)?;
+      let path = Path::new(self.directory).join(flat_name(&prompt.path));
+      let size = content.chars().count();
+      if size > max_size {
+        oversized.push(format!("{} ({} characters)", path.display(), size));
+      }
+      output.write(path, content)?;
+    }
+
+    if !oversized.is_empty() {
+      return Err(AgentError::Template(format!(
+        "{} workflows are limited to {} characters: {}",
+        self.id,
+        max_size,
+        oversized.join(", ")
+      )));
+    }
+    Ok(())
+  }
+
+  fn prunes(&self, path: &Path) -> bool {
+    path.parent() == Some(Path::new(self.directory))
+  }
+}
+
+/// Value of `auto_execution_mode` for a policy
+fn execution_mode(policy: ExecutionPolicy) -> Value {
+  match policy {
+    ExecutionPolicy::Manual => 1.into(),
+    ExecutionPolicy::Auto => 2.into(),
+    ExecutionPolicy::Turbo => 3.into(),
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+  use crate::modules::config::TargetSettings;
+
+  #[test]
+  fn test_workflows_map_execution_policy() {
+    let prompts = [
+      CanonicalPrompt::parse(
+        TemplateType::Command,
+        "pe/compile.md",
+        "---\ndescription: Compile\nexecution: turbo\n---\n\nCompile $1\n",
+      )
+      .unwrap(),
+      CanonicalPrompt::parse(TemplateType::Command, "check.md", "Check.\n").unwrap(),
+      CanonicalPrompt::parse(
+        TemplateType::Command,
+        "bad.md",
+        "---\nexecution: always\n---\n",
+      )
+      .unwrap(),
+    ];
+
+    let temp = tempfile::tempdir().unwrap();
+    let mut output = SyncOutput::new(temp.path());
+    WorkflowTarget::windsurf()
+      .render(&prompts, &mut output)
+      .unwrap();
+
+    assert_eq!(
+      output.file_str(".windsurf/workflows/pe_compile.md"),
+      "---\ndescription: Compile\nauto_execution_mode: 3\n---\n\nCompile $1\n"
+    );
+    assert_eq!(output.file_str(".windsurf/workflows/check.md"), "Check.\n");
+    assert_eq!(output.files().len(), 2);
+    assert!(
+      output.skipped()[0]
+        .1
+        .contains("unknown execution policy 'always'")
+    );
+
+    let mut output = SyncOutput::new(temp.path()).with_settings(TargetSettings {
+      max_size: Some(10),
+      ..Default::default()
+    });
+    let error = WorkflowTarget::antigravity()
+      .render(&prompts, &mut output)
+      .unwrap_err()
+      .to_string();
+    assert!(error.contains(".agent/workflows/pe_compile.md (64 characters)"));
+    assert!(!error.contains("check.md"));
+  }
+}
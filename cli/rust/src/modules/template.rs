@@ -2,19 +2,181 @@
 //!
 //! This module provides template registry, loading, and processing functionality.
 
-use super::config::{PromptTemplate, TemplateType, TemplateVariable, VariableType};
-use super::error::{AgentError, Result};
+use super::config::{HelperSettings, PromptTemplate, TemplateType, TemplateVariable, VariableType};
+use super::error::{AgentError, Result, VariableViolation};
+use super::frontmatter;
+use super::helpers;
 use handlebars::Handlebars;
+use regex::Regex;
+use semver::{Version, VersionReq};
 use serde::{Deserialize, Serialize};
-use std::collections::HashMap;
+use std::collections::{BTreeMap, HashMap, HashSet};
+use std::fmt;
 use std::path::Path;
+use std::sync::LazyLock;
+
+/// Matches partial invocations: `{{> name}}` and partial blocks `{{#> name}}`
+static PARTIAL_REFERENCE: LazyLock<Regex> = LazyLock::new(|| {
+  Regex::new(r#"\{\{~?\s*(#)?>\s*([A-Za-z0-9_\-./@]+)"#).expect("valid partial regex")
+});
+
+/// Matches inline partial definitions: `{{#*inline "name"}}`
+static INLINE_DEFINITION: LazyLock<Regex> =
+  LazyLock::new(|| Regex::new(r#"\{\{~?\s*#\*inline\s+"([^"]+)""#).expect("valid inline regex"));
+
+/// Suffix of the snapshot fixture files holding the variables
+pub const FIXTURE_SUFFIX: &str = ".test.json";
+
+/// Suffix of the snapshot files holding the expected output
+pub const EXPECTED_SUFFIX: &str = ".expected.md";
+
+/// Check whether a path is a snapshot fixture or expected output file
+pub fn is_fixture_file(path: &Path) -> bool {
+  path
+    .file_name()
+    .and_then(|s| s.to_str())
+    .is_some_and(|name| name.ends_with(FIXTURE_SUFFIX) || name.ends_with(EXPECTED_SUFFIX))
+}
+
+/// Metadata key holding the minimum agents-core version a template needs
+pub const CORE_VERSION_KEY: &str = "agents-core";
+
+/// Origin of a registered template, ordered by increasing priority
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash, Serialize, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum TemplateSource {
+  /// Templates shipped with agents-core
+  BuiltIn,
+  /// Templates fetched from remote packs
+  Remote,
+  /// Templates of the project's local templates directory
+  Project,
+  /// Templates of the user's templates directory
+  User,
+}
+
+impl fmt::Display for TemplateSource {
+  fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+    match self {
+      Self::BuiltIn => write!(f, "built-in"),
+      Self::Remote => write!(f, "remote"),
+      Self::Project => write!(f, "project"),
+      Self::User => write!(f, "user"),
+    }
+  }
+}
+
+/// Which source provides the templates registered under a name
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Provenance {
+  /// Template name
+  pub name: String,
+  /// Source whose versions are used
+  pub source: TemplateSource,
+  /// Versions provided by the winning source, oldest first
+  pub versions: Vec<Version>,
+  /// Lower priority sources providing the name, highest first
+  pub overridden: Vec<TemplateSource>,
+}
 
 /// Template registry for managing prompt templates
+///
+/// Several versions of a template may be registered side by side. A template is
+/// addressed as `name` (latest version), `name@1.2.3` (exact version) or
+/// `name@^1.2` (highest version matching the range).
+///
+/// When several sources provide the same name, only the versions of the highest
+/// priority source are visible: user templates override project templates, which
+/// override remote and built-in ones. Listings are ordered by name.
 pub struct TemplateRegistry {
-  templates: HashMap<String, PromptTemplate>,
+  templates: BTreeMap<String, BTreeMap<TemplateSource, BTreeMap<Version, PromptTemplate>>>,
+  partials: HashMap<String, String>,
   handlebars: Handlebars<'static>,
 }
 
+/// Front matter of a Markdown template, whose body is the template content
+#[derive(Debug, Clone, Serialize, Deserialize)]
+struct TemplateFrontMatter {
+  name: String,
+  #[serde(default)]
+  description: String,
+  #[serde(default = "default_version")]
+  version: String,
+  r#type: TemplateType,
+  #[serde(default, skip_serializing_if = "Vec::is_empty")]
+  variables: Vec<TemplateVariable>,
+  #[serde(default, skip_serializing_if = "BTreeMap::is_empty")]
+  metadata: BTreeMap<String, String>,
+  #[serde(default, skip_serializing_if = "Option::is_none")]
+  extends: Option<String>,
+  #[serde(default, skip_serializing_if = "BTreeMap::is_empty")]
+  blocks: BTreeMap<String, String>,
+  #[serde(default, skip_serializing_if = "std::ops::Not::not")]
+  default: bool,
+}
+
+fn default_version() -> String {
+  "1.0.0".to_string()
+}
+
+impl TemplateFrontMatter {
+  fn into_template(self, content: String) -> PromptTemplate {
+    PromptTemplate {
+      name: self.name,
+      description: self.description,
+      version: self.version,
+      r#type: self.r#type,
+      content,
+      metadata: self.metadata.into_iter().collect(),
+      variables: self.variables,
+      extends: self.extends,
+      blocks: self.blocks,
+      default: self.default,
+    }
+  }
+
+  fn from_template(template: &PromptTemplate) -> Self {
+    Self {
+      name: template.name.clone(),
+      description: template.description.clone(),
+      version: template.version.clone(),
+      r#type: template.r#type.clone(),
+      variables: template.variables.clone(),
+      metadata: template
+        .metadata
+        .iter()
+        .map(|(k, v)| (k.clone(), v.clone()))
+        .collect(),
+      extends: template.extends.clone(),
+      blocks: template.blocks.clone(),
+      default: template.default,
+    }
+  }
+}
+
+/// A newer major version of a template offered by a namespaced pack
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct MajorUpgrade {
+  /// Name of the template in use
+  pub template: String,
+  /// Latest version in use
+  pub current: Version,
+  /// Newer major version available
+  pub available: Version,
+  /// Qualified name of the template offering the newer version
+  pub source: String,
+}
+
+impl fmt::Display for MajorUpgrade {
+  fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+    write!(
+      f,
+      "{} {} -> {} available from {}",
+      self.template, self.current, self.available, self.source
+    )
+  }
+}
+
 /// Template processing context
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct TemplateContext {
@@ -32,123 +194,748 @@ impl TemplateRegistry {
     // Configure handlebars
     handlebars.set_strict_mode(true);
     handlebars.register_escape_fn(handlebars::no_escape);
+    helpers::register_builtin_helpers(&mut handlebars, Path::new("."), &HelperSettings::default());
 
-    Ok(Self {
-      templates: HashMap::new(),
+    let mut registry = Self {
+      templates: BTreeMap::new(),
+      partials: HashMap::new(),
       handlebars,
-    })
+    };
+
+    for (name, content) in Self::get_default_partials() {
+      registry.register_partial(name, content)?;
+    }
+
+    Ok(registry)
+  }
+
+  /// Configure the helpers for a project root and register the project helper rules
+  pub fn configure_helpers<P: AsRef<Path>>(
+    &mut self,
+    root: P,
+    settings: &HelperSettings,
+  ) -> Result<()> {
+    helpers::register_builtin_helpers(&mut self.handlebars, root.as_ref(), settings);
+    helpers::register_rule_helpers(&mut self.handlebars, &settings.rules)
   }
 
-  /// Register a new template
+  /// Register a new project template
+  ///
+  /// A template with `extends` is compiled into its `blocks` as inline partials,
+  /// followed by its own content and a call to the parent template. Its content
+  /// should therefore only hold additional `{{#*inline}}` definitions. The parent
+  /// must already be registered; `extends` accepts the same `name@range` syntax as
+  /// [`Self::resolve_template`].
+  ///
+  /// The version must be valid semver. Registering an identical template again is
+  /// a no-op, while registering different content under an existing version fails.
   pub fn register_template(&mut self, template: PromptTemplate) -> Result<()> {
-    // Validate template syntax
-    if let Err(error) = self
-      .handlebars
-      .register_template_string(&template.name, &template.content)
+    self.register_template_as(template, TemplateSource::Project)
+  }
+
+  /// Register a new template provided by `source`
+  pub fn register_template_as(
+    &mut self,
+    template: PromptTemplate,
+    source: TemplateSource,
+  ) -> Result<()> {
+    if template.name.contains('@') {
+      return Err(AgentError::Template(format!(
+        "Template name '{}' must not contain '@'",
+        template.name
+      )));
+    }
+    if self.partials.contains_key(&template.name) {
+      return Err(AgentError::Template(format!(
+        "Template '{}' conflicts with a partial of the same name",
+        template.name
+      )));
+    }
+    let version = Self::parse_version(&template)?;
+    Self::check_core_version(&template)?;
+
+    if let Some(existing) = self
+      .templates
+      .get(&template.name)
+      .and_then(|sources| sources.get(&source))
+      .and_then(|versions| versions.get(&version))
     {
+      if serde_json::to_value(existing)? == serde_json::to_value(&template)? {
+        return Ok(());
+      }
+      return Err(AgentError::Template(format!(
+        "Template '{}' version {} is already registered from {} with different content; bump its version",
+        template.name, version, source
+      )));
+    }
+
+    if let Some(parent) = &template.extends
+      && self.resolve_key(parent).is_none()
+    {
+      return Err(AgentError::Template(format!(
+        "Template '{}' extends unknown template '{}'",
+        template.name, parent
+      )));
+    }
+
+    let key = format!("{}@{}", template.name, version);
+    let compiled = self.compile_source(&template);
+    self.check_source(&key, &compiled)?;
+
+    // Validate template syntax
+    if let Err(error) = handlebars::Template::compile(&compiled) {
       return Err(AgentError::Template(format!(
         "Invalid template syntax: {}",
         error
       )));
     }
 
-    self.templates.insert(template.name.clone(), template);
+    let name = template.name.clone();
+    self
+      .templates
+      .entry(name.clone())
+      .or_default()
+      .entry(source)
+      .or_default()
+      .insert(version, template);
+    self.publish(&name)
+  }
+
+  /// Register the visible versions of a name with handlebars, under `name@version`
+  /// for each version of the winning source and `name` for its latest version
+  fn publish(&mut self, name: &str) -> Result<()> {
+    let Some(sources) = self.templates.get(name) else {
+      self.handlebars.unregister_template(name);
+      return Ok(());
+    };
+
+    let mut compiled = Vec::new();
+    for versions in sources.values() {
+      for version in versions.keys() {
+        self
+          .handlebars
+          .unregister_template(&format!("{}@{}", name, version));
+      }
+    }
+    if let Some(versions) = sources.values().next_back() {
+      for (version, template) in versions {
+        compiled.push((
+          format!("{}@{}", name, version),
+          self.compile_source(template),
+        ));
+      }
+    }
+    if let Some((_, latest)) = compiled.last() {
+      compiled.push((name.to_string(), latest.clone()));
+    } else {
+      self.handlebars.unregister_template(name);
+    }
+
+    for (key, source) in compiled {
+      self
+        .handlebars
+        .register_template_string(&key, &source)
+        .map_err(|e| AgentError::Template(format!("Invalid template syntax: {}", e)))?;
+    }
     Ok(())
   }
 
-  /// Get a template by name
-  pub fn get_template(&self, name: &str) -> Option<&PromptTemplate> {
-    self.templates.get(name)
+  /// Parse the semver version of a template
+  fn parse_version(template: &PromptTemplate) -> Result<Version> {
+    Version::parse(&template.version).map_err(|e| {
+      AgentError::Template(format!(
+        "Template '{}' has invalid version '{}': {}",
+        template.name, template.version, e
+      ))
+    })
+  }
+
+  /// Check the minimum agents-core version declared in the template metadata
+  ///
+  /// The value is either a plain version, read as a minimum, or a semver range.
+  fn check_core_version(template: &PromptTemplate) -> Result<()> {
+    let Some(required) = template.metadata.get(CORE_VERSION_KEY) else {
+      return Ok(());
+    };
+    let requirement = match Version::parse(required) {
+      Ok(minimum) => VersionReq::parse(&format!(">={}", minimum)),
+      Err(_) => VersionReq::parse(required),
+    }
+    .map_err(|e| {
+      AgentError::Template(format!(
+        "Template '{}' has invalid {} requirement '{}': {}",
+        template.name, CORE_VERSION_KEY, required, e
+      ))
+    })?;
+
+    let current = Version::parse(crate::VERSION)
+      .map_err(|e| AgentError::Internal(format!("Invalid crate version: {}", e)))?;
+    if !requirement.matches(&current) {
+      return Err(AgentError::Template(format!(
+        "Template '{}' requires {} {}, but this is {}",
+        template.name, CORE_VERSION_KEY, required, current
+      )));
+    }
+    Ok(())
+  }
+
+  /// Register the built-in templates
+  pub fn register_builtin_templates(&mut self) -> Result<usize> {
+    self.register_templates_as(Self::get_default_templates(), TemplateSource::BuiltIn)
+  }
+
+  /// Register several project templates, ordering them so parents precede their children
+  pub fn register_templates(&mut self, templates: Vec<PromptTemplate>) -> Result<usize> {
+    self.register_templates_as(templates, TemplateSource::Project)
+  }
+
+  /// Register several templates provided by `source`, parents first
+  pub fn register_templates_as(
+    &mut self,
+    templates: Vec<PromptTemplate>,
+    source: TemplateSource,
+  ) -> Result<usize> {
+    let mut pending = templates;
+    let mut registered = 0;
+
+    while !pending.is_empty() {
+      let (ready, waiting): (Vec<_>, Vec<_>) = pending.into_iter().partition(|t| {
+        t.extends
+          .as_ref()
+          .is_none_or(|parent| self.resolve_key(parent).is_some())
+      });
+
+      if ready.is_empty() {
+        let names: Vec<String> = waiting
+          .iter()
+          .map(|t| format!("{} -> {}", t.name, t.extends.as_deref().unwrap_or("")))
+          .collect();
+        return Err(AgentError::Template(format!(
+          "Unresolvable template inheritance (missing parent or cycle): {}",
+          names.join(", ")
+        )));
+      }
+
+      for template in ready {
+        self.register_template_as(template, source)?;
+        registered += 1;
+      }
+      pending = waiting;
+    }
+
+    Ok(registered)
+  }
+
+  /// Register a reusable partial, usable from templates via `{{> name}}`
+  pub fn register_partial<N: Into<String>, C: Into<String>>(
+    &mut self,
+    name: N,
+    content: C,
+  ) -> Result<()> {
+    let name = name.into();
+    let content = content.into();
+    if self.templates.contains_key(&name) {
+      return Err(AgentError::Template(format!(
+        "Partial '{}' conflicts with a template of the same name",
+        name
+      )));
+    }
+
+    self.check_source(&name, &content)?;
+    self
+      .handlebars
+      .register_partial(&name, &content)
+      .map_err(|e| AgentError::Template(format!("Invalid partial syntax: {}", e)))?;
+    self.partials.insert(name, content);
+    Ok(())
+  }
+
+  /// Load partials from the `*.md` files of a directory, named after their file stem
+  pub fn load_partials_from_directory<P: AsRef<Path>>(&mut self, dir: P) -> Result<usize> {
+    let dir = dir.as_ref();
+    if !dir.exists() {
+      return Ok(0);
+    }
+
+    let mut partials = BTreeMap::new();
+    for entry in std::fs::read_dir(dir)
+      .map_err(|e| AgentError::Storage(format!("Failed to read partials directory: {}", e)))?
+    {
+      let entry =
+        entry.map_err(|e| AgentError::Storage(format!("Failed to read directory entry: {}", e)))?;
+      let path = entry.path();
+
+      if path.is_file()
+        && path.extension().and_then(|s| s.to_str()) == Some("md")
+        && let Some(name) = path.file_stem().and_then(|s| s.to_str())
+      {
+        let content = std::fs::read_to_string(&path)
+          .map_err(|e| AgentError::Storage(format!("Failed to read partial file: {}", e)))?;
+        partials.insert(name.to_string(), content);
+      }
+    }
+
+    // Partials may reference each other, so register them before checking references
+    for (name, content) in &partials {
+      self
+        .handlebars
+        .register_partial(name, content)
+        .map_err(|e| AgentError::Template(format!("Invalid partial syntax in {}: {}", name, e)))?;
+    }
+    self.partials.extend(partials.clone());
+    for (name, content) in &partials {
+      self.check_source(name, content)?;
+    }
+
+    Ok(partials.len())
+  }
+
+  /// Get a partial by name
+  pub fn get_partial(&self, name: &str) -> Option<&str> {
+    self.partials.get(name).map(|s| s.as_str())
+  }
+
+  /// Remove a partial
+  pub fn remove_partial(&mut self, name: &str) -> Option<String> {
+    self.handlebars.unregister_template(name);
+    self.partials.remove(name)
+  }
+
+  /// Build the handlebars source of a template, resolving `extends` and `blocks`
+  ///
+  /// A versioned parent is pinned to the exact version matching it, while a bare
+  /// parent name follows its latest version.
+  fn compile_source(&self, template: &PromptTemplate) -> String {
+    let Some(parent) = &template.extends else {
+      return template.content.clone();
+    };
+    let parent = if parent.contains('@') {
+      self.resolve_key(parent).unwrap_or_else(|| parent.clone())
+    } else {
+      parent.clone()
+    };
+
+    let mut source = String::new();
+    for (block, body) in &template.blocks {
+      source.push_str(&format!(
+        "{{{{#*inline \"{}\"}}}}{}{{{{/inline}}}}",
+        block, body
+      ));
+    }
+    source.push_str(&template.content);
+    source.push_str(&format!("{{{{> {}}}}}", parent));
+    source
+  }
+
+  /// Check that every partial referenced by `source` exists and that registering
+  /// it under `name` would not introduce a cycle
+  fn check_source(&self, name: &str, source: &str) -> Result<()> {
+    let inline: HashSet<&str> = INLINE_DEFINITION
+      .captures_iter(source)
+      .filter_map(|c| c.get(1).map(|m| m.as_str()))
+      .collect();
+
+    for (is_block, reference) in Self::partial_references(source) {
+      if is_block || inline.contains(reference.as_str()) {
+        continue;
+      }
+      if !Self::refers_to(&reference, name) && !self.is_known(&reference) {
+        return Err(AgentError::Template(format!(
+          "'{}' references missing partial '{}'",
+          name, reference
+        )));
+      }
+    }
+
+    let mut path = vec![name.to_string()];
+    if self.reaches(name, source, &mut path) {
+      return Err(AgentError::Template(format!(
+        "Partial or inheritance cycle detected: {}",
+        path.join(" -> ")
+      )));
+    }
+    Ok(())
+  }
+
+  /// Depth-first search for a path from `source` back to `target`
+  fn reaches(&self, target: &str, source: &str, path: &mut Vec<String>) -> bool {
+    for (_, reference) in Self::partial_references(source) {
+      if Self::refers_to(&reference, target) {
+        path.push(reference);
+        return true;
+      }
+      if path.contains(&reference) {
+        continue;
+      }
+      let Some(next) = self.source_of(&reference) else {
+        continue;
+      };
+      path.push(reference);
+      if self.reaches(target, &next, path) {
+        return true;
+      }
+      path.pop();
+    }
+    false
+  }
+
+  /// Get the registered handlebars source of a template or partial
+  fn source_of(&self, name: &str) -> Option<String> {
+    self
+      .resolve_template(name)
+      .ok()
+      .map(|template| self.compile_source(template))
+      .or_else(|| self.partials.get(name).cloned())
+  }
+
+  fn is_known(&self, name: &str) -> bool {
+    self.partials.contains_key(name) || self.resolve_key(name).is_some()
+  }
+
+  /// Check whether a reference points at `target`, where a bare template name
+  /// refers to the `name@version` being registered since it becomes the latest
+  fn refers_to(reference: &str, target: &str) -> bool {
+    reference == target
+      || target
+        .split_once('@')
+        .is_some_and(|(name, _)| name == reference)
+  }
+
+  /// Resolve a template reference to its exact `name@version` handlebars key
+  fn resolve_key(&self, spec: &str) -> Option<String> {
+    self
+      .resolve_template(spec)
+      .ok()
+      .map(|template| format!("{}@{}", template.name, template.version))
+  }
+
+  /// Check whether a source references a partial
+  pub fn uses_partials(source: &str) -> bool {
+    PARTIAL_REFERENCE.is_match(source)
   }
 
-  /// List all registered templates
+  /// Extract `(is_block, name)` pairs of partial references from a source
+  fn partial_references(source: &str) -> Vec<(bool, String)> {
+    PARTIAL_REFERENCE
+      .captures_iter(source)
+      .filter_map(|c| Some((c.get(1).is_some(), c.get(2)?.as_str().to_string())))
+      .collect()
+  }
+
+  /// Get a template by name, `name@version` or `name@range`
+  pub fn get_template(&self, spec: &str) -> Option<&PromptTemplate> {
+    self.resolve_template(spec).ok()
+  }
+
+  /// Resolve a template reference
+  ///
+  /// `name` selects the latest version, `name@1.2.3` that exact version and
+  /// `name@^1.2` (or any other semver range) the highest matching version.
+  pub fn resolve_template(&self, spec: &str) -> Result<&PromptTemplate> {
+    let (name, requirement) = match spec.split_once('@') {
+      Some((name, requirement)) => (name, Some(requirement.trim())),
+      None => (spec, None),
+    };
+    let versions = self
+      .visible(name)
+      .ok_or_else(|| AgentError::Template(format!("Template not found: {}", name)))?;
+
+    let found = match requirement {
+      None => versions.values().next_back(),
+      Some(requirement) => match Version::parse(requirement) {
+        Ok(exact) => versions.get(&exact),
+        Err(_) => {
+          let range = VersionReq::parse(requirement).map_err(|e| {
+            AgentError::Template(format!(
+              "Invalid version range '{}' for '{}': {}",
+              requirement, name, e
+            ))
+          })?;
+          versions
+            .iter()
+            .rev()
+            .find(|(version, _)| range.matches(version))
+            .map(|(_, template)| template)
+        }
+      },
+    };
+
+    found.ok_or_else(|| {
+      let available: Vec<String> = versions.keys().map(|v| v.to_string()).collect();
+      AgentError::Template(format!(
+        "No version of '{}' matches '{}' (available: {})",
+        name,
+        requirement.unwrap_or("*"),
+        available.join(", ")
+      ))
+    })
+  }
+
+  /// Get the versions of a name provided by its highest priority source
+  fn visible(&self, name: &str) -> Option<&BTreeMap<Version, PromptTemplate>> {
+    self.templates.get(name)?.values().next_back()
+  }
+
+  /// List every visible version of a template, oldest first
+  pub fn list_template_versions(&self, name: &str) -> Vec<&PromptTemplate> {
+    self
+      .visible(name)
+      .map(|versions| versions.values().collect())
+      .unwrap_or_default()
+  }
+
+  /// List the latest visible version of all registered templates, ordered by name
   pub fn list_templates(&self) -> Vec<&PromptTemplate> {
-    self.templates.values().collect()
+    self
+      .templates
+      .keys()
+      .filter_map(|name| self.visible(name)?.values().next_back())
+      .collect()
+  }
+
+  /// Show which source provides the templates registered under a name
+  pub fn provenance(&self, name: &str) -> Option<Provenance> {
+    let sources = self.templates.get(name)?;
+    let (source, versions) = sources.iter().next_back()?;
+    Some(Provenance {
+      name: name.to_string(),
+      source: *source,
+      versions: versions.keys().cloned().collect(),
+      overridden: sources.keys().rev().skip(1).copied().collect(),
+    })
   }
 
-  /// List templates by type
+  /// Get the source providing the templates registered under a name
+  pub fn source_of_template(&self, name: &str) -> Option<TemplateSource> {
+    self.templates.get(name)?.keys().next_back().copied()
+  }
+
+  /// Get the template composed by default for a type
+  ///
+  /// Among the templates of the type marked `default: true`, the one from the
+  /// highest priority source wins, then the first by name. Without any marked
+  /// template, the first template of the type by name is used.
+  pub fn default_template(&self, template_type: &TemplateType) -> Option<&PromptTemplate> {
+    let candidates = self.list_templates_by_type(template_type.clone());
+    candidates
+      .iter()
+      .filter(|t| t.default)
+      .min_by_key(|t| (std::cmp::Reverse(self.source_of_template(&t.name)), &t.name))
+      .or(candidates.first())
+      .copied()
+  }
+
+  /// List the latest version of templates by type
   pub fn list_templates_by_type(&self, template_type: TemplateType) -> Vec<&PromptTemplate> {
     self
-      .templates
-      .values()
+      .list_templates()
+      .into_iter()
       .filter(|t| t.r#type == template_type)
       .collect()
   }
 
+  /// Find templates for which a namespaced pack offers a newer major version
+  ///
+  /// A pack template `namespace/name` is compared with the latest registered
+  /// version of `name`.
+  pub fn major_upgrades(&self) -> Vec<MajorUpgrade> {
+    let mut upgrades = Vec::new();
+    for qualified in self.templates.keys() {
+      let Some((_, name)) = qualified.rsplit_once('/') else {
+        continue;
+      };
+      let (Some(current), Some(available)) = (
+        self
+          .visible(name)
+          .and_then(|versions| versions.keys().next_back()),
+        self
+          .visible(qualified)
+          .and_then(|versions| versions.keys().next_back()),
+      ) else {
+        continue;
+      };
+      if available.major > current.major {
+        upgrades.push(MajorUpgrade {
+          template: name.to_string(),
+          current: current.clone(),
+          available: available.clone(),
+          source: qualified.clone(),
+        });
+      }
+    }
+    upgrades.sort_by(|a, b| (&a.template, &a.source).cmp(&(&b.template, &b.source)));
+    upgrades
+  }
+
   /// Render a template with context
-  pub fn render_template(&self, template_name: &str, context: &TemplateContext) -> Result<String> {
-    let template = self
-      .templates
-      .get(template_name)
-      .ok_or_else(|| AgentError::Template(format!("Template not found: {}", template_name)))?;
+  ///
+  /// The template is addressed like in [`Self::resolve_template`].
+  pub fn render_template(&self, spec: &str, context: &TemplateContext) -> Result<String> {
+    let template = self.resolve_template(spec)?;
 
-    // Validate required variables
-    self.validate_variables(template, context)?;
+    // Validate and coerce variables
+    let variables = self.validate_variables(template, context)?;
 
     // Render template
+    let key = format!("{}@{}", template.name, template.version);
+    self
+      .handlebars
+      .render(&key, &variables)
+      .map_err(|e| AgentError::Template(format!("Failed to render template: {}", e)))
+  }
+
+  /// Render a template that need not be registered with already validated variables
+  ///
+  /// Its parent and partials must be registered.
+  pub fn render_source(
+    &self,
+    template: &PromptTemplate,
+    variables: &HashMap<String, serde_json::Value>,
+  ) -> Result<String> {
     self
       .handlebars
-      .render(template_name, context)
+      .render_template(&self.compile_source(template), variables)
       .map_err(|e| AgentError::Template(format!("Failed to render template: {}", e)))
   }
 
-  /// Validate that all required variables are provided
-  fn validate_variables(&self, template: &PromptTemplate, context: &TemplateContext) -> Result<()> {
+  /// Validate the context against the declared variables
+  ///
+  /// Values are coerced to their declared type and checked against the allowed
+  /// values, missing variables fall back to their typed default (or null when
+  /// optional), and every violation is reported at once.
+  pub fn validate_variables(
+    &self,
+    template: &PromptTemplate,
+    context: &TemplateContext,
+  ) -> Result<HashMap<String, serde_json::Value>> {
+    let mut variables = context.variables.clone();
+    let mut violations = Vec::new();
+
     for variable in &template.variables {
-      if variable.required && !context.variables.contains_key(&variable.name) {
-        return Err(AgentError::Template(format!(
-          "Required variable '{}' not provided",
-          variable.name
-        )));
+      let resolved = match context.variables.get(&variable.name) {
+        Some(value) => variable.coerce(value).map(Some),
+        None => variable.default_value().and_then(|default| match default {
+          Some(default) => variable.coerce(&default).map(Some),
+          None if variable.required => Err("required variable not provided".to_string()),
+          None => Ok(None),
+        }),
+      };
+
+      match resolved {
+        Ok(value) => {
+          variables.insert(
+            variable.name.clone(),
+            value.unwrap_or(serde_json::Value::Null),
+          );
+        }
+        Err(message) => violations.push(VariableViolation {
+          variable: variable.name.clone(),
+          message,
+        }),
       }
     }
-    Ok(())
+
+    if violations.is_empty() {
+      Ok(variables)
+    } else {
+      Err(AgentError::InvalidVariables(violations))
+    }
   }
 
-  /// Load templates from a directory
+  /// Load project templates from a directory
   pub fn load_templates_from_directory<P: AsRef<Path>>(&mut self, dir: P) -> Result<usize> {
+    self.load_namespaced_templates(dir, None, TemplateSource::Project)
+  }
+
+  /// Load templates provided by `source` from a directory, prefixing their names
+  /// with `namespace/`
+  pub fn load_namespaced_templates<P: AsRef<Path>>(
+    &mut self,
+    dir: P,
+    namespace: Option<&str>,
+    source: TemplateSource,
+  ) -> Result<usize> {
     let dir = dir.as_ref();
     if !dir.exists() {
       return Ok(0);
     }
 
-    let mut loaded_count = 0;
-
+    let mut paths = Vec::new();
     for entry in std::fs::read_dir(dir)
       .map_err(|e| AgentError::Storage(format!("Failed to read templates directory: {}", e)))?
     {
       let entry =
         entry.map_err(|e| AgentError::Storage(format!("Failed to read directory entry: {}", e)))?;
       let path = entry.path();
+      if path.is_file() && Self::is_template_file(&path) {
+        paths.push(path);
+      }
+    }
+    paths.sort();
 
-      if path.is_file()
-        && path.extension().and_then(|s| s.to_str()) == Some("json")
-        && let Ok(template) = self.load_template_from_file(&path)
-      {
-        self.register_template(template)?;
-        loaded_count += 1;
+    let mut templates: Vec<PromptTemplate> = paths
+      .iter()
+      .filter_map(|path| self.load_template_from_file(path).ok())
+      .collect();
+
+    if let Some(namespace) = namespace {
+      let names: HashSet<String> = templates.iter().map(|t| t.name.clone()).collect();
+      for template in &mut templates {
+        template.name = format!("{}/{}", namespace, template.name);
+        // Parents from the same directory move into the namespace as well
+        if let Some(parent) = &template.extends
+          && names.contains(parent.split('@').next().unwrap_or(parent))
+        {
+          template.extends = Some(format!("{}/{}", namespace, parent));
+        }
       }
     }
-    Ok(loaded_count)
+
+    self.register_templates_as(templates, source)
+  }
+
+  /// Check whether a path has a template file extension (`.json` or `.md`) and is
+  /// not a snapshot fixture
+  pub fn is_template_file(path: &Path) -> bool {
+    matches!(
+      path.extension().and_then(|s| s.to_str()),
+      Some("json") | Some("md")
+    ) && !is_fixture_file(path)
   }
 
-  /// Load a single template from a file
+  /// Load a single template from a JSON file or a Markdown file with front matter
   pub fn load_template_from_file<P: AsRef<Path>>(&self, file_path: P) -> Result<PromptTemplate> {
+    let file_path = file_path.as_ref();
     let content = std::fs::read_to_string(file_path)
       .map_err(|e| AgentError::Storage(format!("Failed to read template file: {}", e)))?;
 
+    if Self::is_markdown(file_path) {
+      let (front_matter, body): (TemplateFrontMatter, String) = frontmatter::parse(&content)
+        .map_err(|e| AgentError::Template(format!("Failed to parse template file: {}", e)))?;
+      return Ok(front_matter.into_template(body));
+    }
+
     serde_json::from_str(&content)
       .map_err(|e| AgentError::Template(format!("Failed to parse template file: {}", e)))
   }
 
-  /// Save a template to a file
+  /// Save a template to a file, as Markdown with front matter for `.md` paths and
+  /// as JSON otherwise
   pub fn save_template_to_file<P: AsRef<Path>>(
     &self,
     template: &PromptTemplate,
     file_path: P,
   ) -> Result<()> {
-    let content = serde_json::to_string_pretty(template)
-      .map_err(|e| AgentError::Template(format!("Failed to serialize template: {}", e)))?;
+    let content = if Self::is_markdown(file_path.as_ref()) {
+      frontmatter::render(
+        &TemplateFrontMatter::from_template(template),
+        &template.content,
+      )
+      .map_err(|e| AgentError::Template(format!("Failed to serialize template: {}", e)))?
+    } else {
+      serde_json::to_string_pretty(template)
+        .map_err(|e| AgentError::Template(format!("Failed to serialize template: {}", e)))?
+    };
 
     // Ensure parent directory exists
     if let Some(parent) = file_path.as_ref().parent() {
@@ -160,10 +947,34 @@ impl TemplateRegistry {
       .map_err(|e| AgentError::Storage(format!("Failed to write template file: {}", e)))
   }
 
-  /// Remove a template
-  pub fn remove_template(&mut self, name: &str) -> Option<PromptTemplate> {
-    self.handlebars.unregister_template(name);
-    self.templates.remove(name)
+  fn is_markdown(path: &Path) -> bool {
+    path.extension().and_then(|s| s.to_str()) == Some("md")
+  }
+
+  /// Remove one visible version of a template, or its latest version for a bare
+  /// name; versions of lower priority sources become visible once none is left
+  pub fn remove_template(&mut self, spec: &str) -> Option<PromptTemplate> {
+    let template = self.resolve_template(spec).ok()?;
+    let name = template.name.clone();
+    let version = Version::parse(&template.version).ok()?;
+
+    let sources = self.templates.get_mut(&name)?;
+    let (&source, versions) = sources.iter_mut().next_back()?;
+    let removed = versions.remove(&version)?;
+    self
+      .handlebars
+      .unregister_template(&format!("{}@{}", name, version));
+    if versions.is_empty() {
+      sources.remove(&source);
+    }
+    if sources.is_empty() {
+      self.templates.remove(&name);
+    }
+
+    if let Err(error) = self.publish(&name) {
+      tracing::warn!("Failed to re-register template '{}': {}", name, error);
+    }
+    Some(removed)
   }
 
   /// Create a template context from variables
@@ -179,15 +990,76 @@ impl TemplateRegistry {
     context.metadata.insert(key, value);
   }
 
+  /// Load variable values from a JSON or TOML file
+  pub fn load_variables<P: AsRef<Path>>(path: P) -> Result<HashMap<String, serde_json::Value>> {
+    let path = path.as_ref();
+    let content = std::fs::read_to_string(path)
+      .map_err(|e| AgentError::FileNotFound(format!("{}: {}", path.display(), e)))?;
+
+    let value: serde_json::Value = match path.extension().and_then(|e| e.to_str()) {
+      Some("toml") => {
+        let table: toml::Table = toml::from_str(&content)?;
+        serde_json::to_value(table)?
+      }
+      Some("json") => serde_json::from_str(&content)?,
+      _ => {
+        return Err(AgentError::Template(format!(
+          "Unsupported variables file {}: expected .json or .toml",
+          path.display()
+        )));
+      }
+    };
+
+    match value {
+      serde_json::Value::Object(map) => Ok(map.into_iter().collect()),
+      _ => Err(AgentError::Template(format!(
+        "Variables file {} must contain an object",
+        path.display()
+      ))),
+    }
+  }
+
+  /// Parse a `key=value` assignment; the value is coerced when rendering
+  pub fn parse_assignment(assignment: &str) -> Result<(String, serde_json::Value)> {
+    let (key, value) = assignment.split_once('=').ok_or_else(|| {
+      AgentError::Template(format!(
+        "Invalid assignment '{}': expected key=value",
+        assignment
+      ))
+    })?;
+    let key = key.trim();
+    if key.is_empty() {
+      return Err(AgentError::Template(format!(
+        "Invalid assignment '{}': empty key",
+        assignment
+      )));
+    }
+    Ok((
+      key.to_string(),
+      serde_json::Value::String(value.to_string()),
+    ))
+  }
+
+  /// Get the built-in partials shared by the default templates
+  pub fn get_default_partials() -> Vec<(&'static str, &'static str)> {
+    vec![
+      (
+        "section-instructions",
+        "## Instructions\n{{instructions}}\n",
+      ),
+      ("section-examples", "## Examples\n{{examples}}\n"),
+    ]
+  }
+
   /// Get default templates for each type
   pub fn get_default_templates() -> Vec<PromptTemplate> {
     vec![
-      // Memory template
+      // Locale memory template
       PromptTemplate {
         name: "memory-default".to_string(),
-        description: "Default memory prompt template".to_string(),
+        description: "Default locale memory prompt template".to_string(),
         version: "1.0.0".to_string(),
-        r#type: TemplateType::Memory,
+        r#type: TemplateType::LocaleMemory,
         content: r#"# {{title}}
 
 ## Description
@@ -196,11 +1068,9 @@ impl TemplateRegistry {
 ## Context
 {{context}}
 
-## Instructions
-{{instructions}}
+{{> section-instructions}}
 
-## Examples
-{{examples}}
+{{> section-examples}}
 "#
         .to_string(),
         metadata: {
@@ -214,9 +1084,10 @@ impl TemplateRegistry {
             name: "title".to_string(),
             description: "Title of the memory prompt".to_string(),
             r#type: VariableType::String,
-            required: true,
+            required: false,
             default: Some("Memory Prompt".to_string()),
             values: None,
+            multiline: false,
           },
           TemplateVariable {
             name: "description".to_string(),
@@ -225,6 +1096,7 @@ impl TemplateRegistry {
             required: true,
             default: None,
             values: None,
+            multiline: true,
           },
           TemplateVariable {
             name: "context".to_string(),
@@ -233,6 +1105,7 @@ impl TemplateRegistry {
             required: false,
             default: None,
             values: None,
+            multiline: true,
           },
           TemplateVariable {
             name: "instructions".to_string(),
@@ -241,6 +1114,7 @@ impl TemplateRegistry {
             required: true,
             default: None,
             values: None,
+            multiline: true,
           },
           TemplateVariable {
             name: "examples".to_string(),
@@ -249,8 +1123,12 @@ impl TemplateRegistry {
             required: false,
             default: None,
             values: None,
+            multiline: true,
           },
         ],
+        extends: None,
+        blocks: BTreeMap::new(),
+        default: true,
       },
       // Sub-agent template
       PromptTemplate {
@@ -266,8 +1144,7 @@ impl TemplateRegistry {
 ## Capabilities
 {{capabilities}}
 
-## Instructions
-{{instructions}}
+{{> section-instructions}}
 
 ## Constraints
 {{constraints}}
@@ -275,8 +1152,7 @@ impl TemplateRegistry {
 ## Tools Available
 {{tools}}
 
-## Examples
-{{examples}}
+{{> section-examples}}
 "#
         .to_string(),
         metadata: {
@@ -293,6 +1169,7 @@ impl TemplateRegistry {
             required: true,
             default: None,
             values: None,
+            multiline: false,
           },
           TemplateVariable {
             name: "role".to_string(),
@@ -301,6 +1178,7 @@ impl TemplateRegistry {
             required: true,
             default: None,
             values: None,
+            multiline: true,
           },
           TemplateVariable {
             name: "capabilities".to_string(),
@@ -309,6 +1187,7 @@ impl TemplateRegistry {
             required: true,
             default: None,
             values: None,
+            multiline: true,
           },
           TemplateVariable {
             name: "instructions".to_string(),
@@ -317,6 +1196,7 @@ impl TemplateRegistry {
             required: true,
             default: None,
             values: None,
+            multiline: true,
           },
           TemplateVariable {
             name: "constraints".to_string(),
@@ -325,6 +1205,7 @@ impl TemplateRegistry {
             required: false,
             default: None,
             values: None,
+            multiline: true,
           },
           TemplateVariable {
             name: "tools".to_string(),
@@ -333,6 +1214,7 @@ impl TemplateRegistry {
             required: false,
             default: None,
             values: None,
+            multiline: true,
           },
           TemplateVariable {
             name: "examples".to_string(),
@@ -341,8 +1223,12 @@ impl TemplateRegistry {
             required: false,
             default: None,
             values: None,
+            multiline: true,
           },
         ],
+        extends: None,
+        blocks: BTreeMap::new(),
+        default: true,
       },
       // Command template
       PromptTemplate {
@@ -363,8 +1249,7 @@ impl TemplateRegistry {
 ## Arguments
 {{arguments}}
 
-## Examples
-{{examples}}
+{{> section-examples}}
 
 ## Notes
 {{notes}}
@@ -384,6 +1269,7 @@ impl TemplateRegistry {
             required: true,
             default: None,
             values: None,
+            multiline: false,
           },
           TemplateVariable {
             name: "description".to_string(),
@@ -392,6 +1278,7 @@ impl TemplateRegistry {
             required: true,
             default: None,
             values: None,
+            multiline: true,
           },
           TemplateVariable {
             name: "usage".to_string(),
@@ -400,6 +1287,7 @@ impl TemplateRegistry {
             required: true,
             default: None,
             values: None,
+            multiline: false,
           },
           TemplateVariable {
             name: "arguments".to_string(),
@@ -408,6 +1296,7 @@ impl TemplateRegistry {
             required: false,
             default: None,
             values: None,
+            multiline: true,
           },
           TemplateVariable {
             name: "examples".to_string(),
@@ -416,6 +1305,7 @@ impl TemplateRegistry {
             required: false,
             default: None,
             values: None,
+            multiline: true,
           },
           TemplateVariable {
             name: "notes".to_string(),
@@ -424,11 +1314,173 @@ impl TemplateRegistry {
             required: false,
             default: None,
             values: None,
+            multiline: true,
           },
         ],
+        extends: None,
+        blocks: BTreeMap::new(),
+        default: true,
       },
+      Self::builtin_template(
+        "user-memory-default",
+        "Default user memory prompt template",
+        TemplateType::UserMemory,
+        r#"# {{title}}
+
+## Preferences
+{{preferences}}
+
+{{> section-instructions}}
+"#,
+        vec![
+          TemplateVariable {
+            default: Some("User Memory".to_string()),
+            ..Self::text_variable("title", "Title of the user memory", false, false)
+          },
+          Self::text_variable(
+            "preferences",
+            "Preferences applying to every project",
+            true,
+            true,
+          ),
+          Self::text_variable(
+            "instructions",
+            "Specific instructions for the AI",
+            true,
+            true,
+          ),
+        ],
+      ),
+      Self::builtin_template(
+        "project-memory-default",
+        "Default project memory prompt template",
+        TemplateType::ProjectMemory,
+        r#"# {{project_name}}
+
+## Overview
+{{overview}}
+
+## Conventions
+{{conventions}}
+
+{{> section-instructions}}
+"#,
+        vec![
+          Self::text_variable("project_name", "Name of the project", true, false),
+          Self::text_variable("overview", "What the project is about", true, true),
+          Self::text_variable(
+            "conventions",
+            "Coding conventions of the project",
+            true,
+            true,
+          ),
+          Self::text_variable(
+            "instructions",
+            "Specific instructions for the AI",
+            true,
+            true,
+          ),
+        ],
+      ),
+      Self::builtin_template(
+        "skill-default",
+        "Default skill prompt template",
+        TemplateType::Skill,
+        r#"# {{name}}
+
+{{description}}
+
+## When to use
+{{when_to_use}}
+
+{{> section-instructions}}
+"#,
+        vec![
+          Self::text_variable(
+            "name",
+            "Name of the skill, lowercase letters, digits and hyphens",
+            true,
+            false,
+          ),
+          Self::text_variable("description", "What the skill does", true, false),
+          Self::text_variable(
+            "when_to_use",
+            "Situations in which the skill applies",
+            true,
+            true,
+          ),
+          Self::text_variable(
+            "instructions",
+            "Steps the AI follows when using the skill",
+            true,
+            true,
+          ),
+        ],
+      ),
+      Self::builtin_template(
+        "rule-default",
+        "Default rule prompt template",
+        TemplateType::Rule,
+        r#"# {{rule_name}}
+
+{{rule}}
+{{#if rationale}}
+
+## Rationale
+{{rationale}}
+{{/if}}
+"#,
+        vec![
+          Self::text_variable("rule_name", "Name of the rule", true, false),
+          Self::text_variable("rule", "What the rule requires", true, true),
+          Self::text_variable("rationale", "Why the rule exists", false, true),
+        ],
+      ),
     ]
   }
+
+  /// Build a built-in default template of a type
+  fn builtin_template(
+    name: &str,
+    description: &str,
+    r#type: TemplateType,
+    content: &str,
+    variables: Vec<TemplateVariable>,
+  ) -> PromptTemplate {
+    PromptTemplate {
+      name: name.to_string(),
+      description: description.to_string(),
+      version: "1.0.0".to_string(),
+      r#type,
+      content: content.to_string(),
+      metadata: HashMap::from([
+        ("author".to_string(), "system".to_string()),
+        ("created".to_string(), "2024-01-01".to_string()),
+      ]),
+      variables,
+      extends: None,
+      blocks: BTreeMap::new(),
+      default: true,
+    }
+  }
+
+  /// Build a string variable of a built-in template
+  fn text_variable(
+    name: &str,
+    description: &str,
+    required: bool,
+    multiline: bool,
+  ) -> TemplateVariable {
+    TemplateVariable {
+      name: name.to_string(),
+      description: description.to_string(),
+      r#type: VariableType::String,
+      required,
+      default: None,
+      values: None,
+      multiline,
+    }
+  }
 }
 
 impl Default for TemplateRegistry {
@@ -437,9 +1489,307 @@ impl Default for TemplateRegistry {
       // Create a minimal registry if initialization fails
       let handlebars = Handlebars::new();
       Self {
-        templates: HashMap::new(),
+        templates: BTreeMap::new(),
+        partials: HashMap::new(),
         handlebars,
       }
     })
   }
 }
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  fn template(name: &str, content: &str) -> PromptTemplate {
+    PromptTemplate {
+      name: name.to_string(),
+      content: content.to_string(),
+      ..PromptTemplate::default()
+    }
+  }
+
+  fn context(variables: serde_json::Value) -> TemplateContext {
+    let variables = serde_json::from_value(variables).unwrap();
+    TemplateRegistry::create_context(variables)
+  }
+
+  #[test]
+  fn test_default_templates_render_with_partials() {
+    let mut registry = TemplateRegistry::new().unwrap();
+    registry
+      .register_templates(TemplateRegistry::get_default_templates())
+      .unwrap();
+
+    let rendered = registry
+      .render_template(
+        "memory-default",
+        &context(serde_json::json!({
+          "title": "T", "description": "D", "context": "C",
+          "instructions": "I", "examples": "E"
+        })),
+      )
+      .unwrap();
+    assert_eq!(
+      rendered,
+      "# T\n\n## Description\nD\n\n## Context\nC\n\n## Instructions\nI\n\n## Examples\nE\n"
+    );
+  }
+
+  #[test]
+  fn test_extends_overrides_blocks() {
+    let mut registry = TemplateRegistry::new().unwrap();
+    let mut child = template("child", "");
+    child.extends = Some("base".to_string());
+    child
+      .blocks
+      .insert("body".to_string(), "custom {{name}}".to_string());
+
+    // Children may be listed before their parents
+    registry
+      .register_templates(vec![
+        child,
+        template("base", "# {{name}}\n{{#> body}}default{{/body}}"),
+      ])
+      .unwrap();
+
+    let ctx = context(serde_json::json!({ "name": "x" }));
+    assert_eq!(
+      registry.render_template("base", &ctx).unwrap(),
+      "# x\ndefault"
+    );
+    assert_eq!(
+      registry.render_template("child", &ctx).unwrap(),
+      "# x\ncustom x"
+    );
+  }
+
+  #[test]
+  fn test_variables_are_coerced_and_violations_collected() {
+    let variable = |name: &str, r#type: VariableType, default: Option<&str>| TemplateVariable {
+      name: name.to_string(),
+      description: String::new(),
+      r#type,
+      required: true,
+      default: default.map(|d| d.to_string()),
+      values: None,
+      multiline: false,
+    };
+    let mut t = template(
+      "typed",
+      "{{count}} {{enabled}} {{join tags \",\"}} {{level}}",
+    );
+    t.variables = vec![
+      variable("count", VariableType::Number, None),
+      variable("enabled", VariableType::Boolean, Some("yes")),
+      variable("tags", VariableType::Array, Some("a, b")),
+      TemplateVariable {
+        values: Some(vec!["low".to_string(), "high".to_string()]),
+        ..variable("level", VariableType::String, None)
+      },
+    ];
+    let mut registry = TemplateRegistry::new().unwrap();
+    registry.register_template(t).unwrap();
+
+    let rendered = registry
+      .render_template(
+        "typed",
+        &context(serde_json::json!({ "count": "3", "level": "low" })),
+      )
+      .unwrap();
+    assert_eq!(rendered, "3 true a,b low");
+
+    let error = registry
+      .render_template(
+        "typed",
+        &context(serde_json::json!({ "count": "many", "enabled": "maybe", "level": "mid" })),
+      )
+      .unwrap_err();
+    match error {
+      AgentError::InvalidVariables(violations) => {
+        let names: Vec<&str> = violations.iter().map(|v| v.variable.as_str()).collect();
+        assert_eq!(names, vec!["count", "enabled", "level"]);
+      }
+      other => panic!("unexpected error: {}", other),
+    }
+  }
+
+  #[test]
+  fn test_markdown_templates_round_trip() {
+    let temp = tempfile::tempdir().unwrap();
+    let registry = TemplateRegistry::new().unwrap();
+    let original = TemplateRegistry::get_default_templates().remove(0);
+    let path = temp.path().join("memory-default.md");
+
+    registry.save_template_to_file(&original, &path).unwrap();
+    let saved = std::fs::read_to_string(&path).unwrap();
+    assert!(saved.starts_with("---\nname: memory-default\n"));
+    assert!(saved.ends_with(&original.content));
+
+    let loaded = registry.load_template_from_file(&path).unwrap();
+    assert_eq!(
+      serde_json::to_value(&loaded).unwrap(),
+      serde_json::to_value(&original).unwrap()
+    );
+  }
+
+  #[test]
+  fn test_missing_partials_and_cycles_are_rejected() {
+    let mut registry = TemplateRegistry::new().unwrap();
+    assert!(
+      registry
+        .register_template(template("a", "{{> nope}}"))
+        .is_err()
+    );
+
+    registry.register_partial("p1", "one").unwrap();
+    registry.register_partial("p2", "{{> p1}}").unwrap();
+    let error = registry.register_partial("p1", "{{> p2}}").unwrap_err();
+    assert!(error.to_string().contains("cycle"));
+
+    let mut a = template("a", "");
+    a.extends = Some("b".to_string());
+    let mut b = template("b", "");
+    b.extends = Some("a".to_string());
+    assert!(registry.register_templates(vec![a, b]).is_err());
+  }
+
+  fn versioned(name: &str, version: &str, content: &str) -> PromptTemplate {
+    PromptTemplate {
+      version: version.to_string(),
+      ..template(name, content)
+    }
+  }
+
+  #[test]
+  fn test_versions_resolve_by_range() {
+    let mut registry = TemplateRegistry::new().unwrap();
+    for version in ["1.0.0", "1.2.0", "1.4.1", "2.0.0"] {
+      registry
+        .register_template(versioned("base", version, version))
+        .unwrap();
+    }
+    let empty = context(serde_json::json!({}));
+
+    assert_eq!(registry.render_template("base", &empty).unwrap(), "2.0.0");
+    assert_eq!(
+      registry.render_template("base@^1.2", &empty).unwrap(),
+      "1.4.1"
+    );
+    assert_eq!(
+      registry.render_template("base@1.2.0", &empty).unwrap(),
+      "1.2.0"
+    );
+    assert!(registry.resolve_template("base@^3").is_err());
+    assert_eq!(registry.list_template_versions("base").len(), 4);
+    assert_eq!(registry.list_templates().len(), 1);
+
+    // A new major may extend the previous one through a range
+    let mut child = versioned("base", "3.0.0", "");
+    child.extends = Some("base@^1".to_string());
+    registry.register_template(child).unwrap();
+    assert_eq!(registry.render_template("base", &empty).unwrap(), "1.4.1");
+
+    // Same version: identical content is a no-op, different content is refused
+    registry
+      .register_template(versioned("base", "1.0.0", "1.0.0"))
+      .unwrap();
+    assert!(
+      registry
+        .register_template(versioned("base", "1.0.0", "changed"))
+        .is_err()
+    );
+    assert!(
+      registry
+        .register_template(versioned("base", "one", ""))
+        .is_err()
+    );
+  }
+
+  #[test]
+  fn test_sources_override_by_priority() {
+    let mut registry = TemplateRegistry::new().unwrap();
+    registry.register_builtin_templates().unwrap();
+    let empty = context(serde_json::json!({}));
+
+    let mut project = template("memory-default", "project");
+    project.version = "0.9.0".to_string();
+    registry.register_template(project).unwrap();
+    registry
+      .register_template_as(template("memory-default", "user"), TemplateSource::User)
+      .unwrap();
+    assert_eq!(
+      registry.render_template("memory-default", &empty).unwrap(),
+      "user"
+    );
+
+    let provenance = registry.provenance("memory-default").unwrap();
+    assert_eq!(provenance.source, TemplateSource::User);
+    assert_eq!(
+      provenance.overridden,
+      vec![TemplateSource::Project, TemplateSource::BuiltIn]
+    );
+    // Only the versions of the winning source are visible
+    assert!(registry.resolve_template("memory-default@0.9.0").is_err());
+
+    registry.remove_template("memory-default").unwrap();
+    assert_eq!(
+      registry.render_template("memory-default", &empty).unwrap(),
+      "project"
+    );
+
+    // Listings are ordered by name and defaults are explicit
+    let mut other = template("a-memory", "");
+    other.r#type = TemplateType::LocaleMemory;
+    registry.register_template(other).unwrap();
+    let names: Vec<&str> = registry
+      .list_templates_by_type(TemplateType::LocaleMemory)
+      .iter()
+      .map(|t| t.name.as_str())
+      .collect();
+    assert_eq!(names, vec!["a-memory", "memory-default"]);
+    assert_eq!(
+      registry
+        .default_template(&TemplateType::Command)
+        .unwrap()
+        .name,
+      "command-default"
+    );
+    assert_eq!(
+      registry
+        .default_template(&TemplateType::LocaleMemory)
+        .unwrap()
+        .name,
+      "a-memory"
+    );
+  }
+
+  #[test]
+  fn test_core_version_and_major_upgrades() {
+    let mut registry = TemplateRegistry::new().unwrap();
+    let mut future = versioned("future", "1.0.0", "");
+    future
+      .metadata
+      .insert(CORE_VERSION_KEY.to_string(), "999.0.0".to_string());
+    let error = registry.register_template(future).unwrap_err();
+    assert!(error.to_string().contains(CORE_VERSION_KEY));
+
+    registry
+      .register_template(versioned("notes", "1.3.0", ""))
+      .unwrap();
+    registry
+      .register_template(versioned("team/notes", "1.9.0", ""))
+      .unwrap();
+    assert!(registry.major_upgrades().is_empty());
+
+    registry
+      .register_template(versioned("team/notes", "2.1.0", ""))
+      .unwrap();
+    let upgrades = registry.major_upgrades();
+    assert_eq!(upgrades.len(), 1);
+    assert_eq!(
+      upgrades[0].to_string(),
+      "notes 1.3.0 -> 2.1.0 available from team/notes"
+    );
+  }
+}
@@ -0,0 +1,378 @@
+//! Interactive composition wizard for the agents core system
+//!
+//! This module drives `compose --interactive`: it lets the user pick a template,
+//! answers one prompt per template variable, previews the rendered prompt and
+//! writes it atomically under the directory mapped to the template type.
+
+use super::config::{PromptTemplate, TemplateType, TemplateVariable, VariableType};
+use super::error::{AgentError, Result};
+use super::skill;
+use super::storage;
+use super::template::{TemplateContext, TemplateRegistry};
+use dialoguer::{Confirm, Editor, Input, Select};
+use serde_json::Value;
+use std::collections::HashMap;
+use std::path::{Component, Path, PathBuf};
+
+/// Kind of prompt used to ask for a variable
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum VariablePrompt {
+  /// Choose one of the enum values
+  Select(Vec<String>),
+  /// Yes/no question for booleans
+  Confirm,
+  /// External editor for long-form text
+  Editor,
+  /// Single line input, validated against the variable type
+  Input,
+}
+
+impl VariablePrompt {
+  /// Choose the prompt kind for a variable
+  pub fn for_variable(variable: &TemplateVariable) -> Self {
+    match (&variable.values, &variable.r#type) {
+      (Some(values), _) if !values.is_empty() => Self::Select(values.clone()),
+      (_, VariableType::Boolean) => Self::Confirm,
+      (_, VariableType::String) if variable.multiline => Self::Editor,
+      _ => Self::Input,
+    }
+  }
+}
+
+/// Result of a completed wizard run
+#[derive(Debug, Clone)]
+pub struct WizardOutcome {
+  /// Template with its content replaced by the rendered prompt
+  pub template: PromptTemplate,
+  /// Path the prompt was written to
+  pub path: PathBuf,
+}
+
+/// Interactive wizard composing a prompt from a template
+pub struct ComposeWizard<'a> {
+  registry: &'a TemplateRegistry,
+  directory: PathBuf,
+}
+
+impl<'a> ComposeWizard<'a> {
+  /// Create a wizard writing prompts under `directory`
+  pub fn new<P: AsRef<Path>>(registry: &'a TemplateRegistry, directory: P) -> Self {
+    Self {
+      registry,
+      directory: directory.as_ref().to_path_buf(),
+    }
+  }
+
+  /// Run the wizard for a template type
+  pub fn run(&self, template_type: &TemplateType) -> Result<WizardOutcome> {
+    let template = self.pick_template(template_type)?;
+
+    let mut variables = HashMap::new();
+    for variable in &template.variables {
+      if let Some(value) = self.ask_variable(variable)? {
+        variables.insert(variable.name.clone(), value);
+      }
+    }
+
+    let context = TemplateRegistry::create_context(variables);
+    let mut rendered = self
+      .registry
+      .render_template(&format!("{}@{}", template.name, template.version), &context)?;
+    let mut default_name = default_file_name(template, &context);
+    if *template_type == TemplateType::Skill {
+      let (front_matter, document) = skill::compose_entry(&rendered, &context.variables)?;
+      rendered = document;
+      default_name = skill::entry_file_name(&front_matter.name);
+    }
+
+    println!(
+      "\n{}\n{}\n{}",
+      "-".repeat(40),
+      rendered.trim_end(),
+      "-".repeat(40)
+    );
+    if !Confirm::new()
+      .with_prompt("Write this prompt?")
+      .default(true)
+      .interact()
+      .map_err(prompt_error)?
+    {
+      return Err(AgentError::Cancelled);
+    }
+
+    let path = self.pick_target(&default_name)?;
+    storage::write_atomic(&path, rendered.as_bytes())?;
+    println!("Created prompt: {}", path.display());
+
+    let mut composed = template.clone();
+    composed.content = rendered;
+    Ok(WizardOutcome {
+      template: composed,
+      path,
+    })
+  }
+
+  /// Let the user pick one of the templates of a type
+  fn pick_template(&self, template_type: &TemplateType) -> Result<&'a PromptTemplate> {
+    let templates = self.registry.list_templates_by_type(template_type.clone());
+    if templates.is_empty() {
+      return Err(AgentError::Template(format!(
+        "No templates found for type {:?}",
+        template_type
+      )));
+    }
+
+    let items: Vec<String> = templates
+      .iter()
+      .map(|t| format!("{} - {}", t.name, t.description))
+      .collect();
+    let selected = self
+      .registry
+      .default_template(template_type)
+      .and_then(|default| templates.iter().position(|t| t.name == default.name))
+      .unwrap_or(0);
+    let index = Select::new()
+      .with_prompt("Template")
+      .items(&items)
+      .default(selected)
+      .interact_opt()
+      .map_err(prompt_error)?
+      .ok_or(AgentError::Cancelled)?;
+    Ok(templates[index])
+  }
+
+  /// Ask for the value of a variable, returning `None` when an optional value is skipped
+  fn ask_variable(&self, variable: &TemplateVariable) -> Result<Option<Value>> {
+    let label = if variable.description.is_empty() {
+      variable.name.clone()
+    } else {
+      format!("{} ({})", variable.name, variable.description)
+    };
+    let default = variable
+      .default_value()
+      .map_err(|message| AgentError::Template(format!("{}: {}", variable.name, message)))?;
+
+    match VariablePrompt::for_variable(variable) {
+      VariablePrompt::Select(values) => {
+        let mut items = values.clone();
+        if !variable.required {
+          items.insert(0, "(skip)".to_string());
+        }
+        let offset = items.len() - values.len();
+        let selected = default
+          .as_ref()
+          .and_then(|d| d.as_str())
+          .and_then(|d| values.iter().position(|v| v == d))
+          .map_or(0, |i| i + offset);
+        let index = Select::new()
+          .with_prompt(label)
+          .items(&items)
+          .default(selected)
+          .interact_opt()
+          .map_err(prompt_error)?
+          .ok_or(AgentError::Cancelled)?;
+        if index < offset {
+          return Ok(None);
+        }
+        variable
+          .coerce(&Value::String(items[index].clone()))
+          .map(Some)
+          .map_err(AgentError::Template)
+      }
+      VariablePrompt::Confirm => {
+        let answer = Confirm::new()
+          .with_prompt(label)
+          .default(default.and_then(|d| d.as_bool()).unwrap_or(false))
+          .interact()
+          .map_err(prompt_error)?;
+        Ok(Some(Value::Bool(answer)))
+      }
+      VariablePrompt::Editor => {
+        let initial = default
+          .as_ref()
+          .and_then(|d| d.as_str())
+          .unwrap_or_default()
+          .to_string();
+        loop {
+          println!("Opening editor for {}...", label);
+          let text = Editor::new()
+            .extension(".md")
+            .edit(&initial)
+            .map_err(prompt_error)?
+            .unwrap_or_else(|| initial.clone());
+          if !text.trim().is_empty() {
+            return Ok(Some(Value::String(text)));
+          }
+          if !variable.required {
+            return Ok(None);
+          }
+          println!("'{}' is required", variable.name);
+        }
+      }
+      VariablePrompt::Input => {
+        let mut input = Input::<String>::new()
+          .with_prompt(label)
+          .allow_empty(!variable.required);
+        if let Some(default) = &default {
+          input = input.default(match default {
+            Value::String(s) => s.clone(),
+            other => other.to_string(),
+          });
+        }
+        let validated = variable.clone();
+        let answer = input
+          .validate_with(move |text: &String| -> std::result::Result<(), String> {
+            if text.is_empty() {
+              return Ok(());
+            }
+            validated.coerce(&Value::String(text.clone())).map(|_| ())
+          })
+          .interact_text()
+          .map_err(prompt_error)?;
+        if answer.is_empty() {
+          return Ok(None);
+        }
+        variable
+          .coerce(&Value::String(answer))
+          .map(Some)
+          .map_err(AgentError::Template)
+      }
+    }
+  }
+
+  /// Ask for the target file under the wizard directory
+  fn pick_target(&self, default_name: &str) -> Result<PathBuf> {
+    loop {
+      let answer: String = Input::new()
+        .with_prompt(format!("File name (under {})", self.directory.display()))
+        .default(default_name.to_string())
+        .interact_text()
+        .map_err(prompt_error)?;
+
+      let path = match resolve_target(&self.directory, &answer) {
+        Ok(path) => path,
+        Err(error) => {
+          println!("{}", error);
+          continue;
+        }
+      };
+      if path.exists()
+        && !Confirm::new()
+          .with_prompt(format!("{} exists. Overwrite?", path.display()))
+          .default(false)
+          .interact()
+          .map_err(prompt_error)?
+      {
+        continue;
+      }
+      return Ok(path);
+    }
+  }
+}
+
+/// Resolve a user supplied file name under a directory, adding `.md` when no
+/// extension is given and refusing paths that escape the directory
+pub fn resolve_target(directory: &Path, name: &str) -> Result<PathBuf> {
+  let relative = Path::new(name.trim());
+  if relative.as_os_str().is_empty()
+    || relative
+      .components()
+      .any(|c| !matches!(c, Component::Normal(_) | Component::CurDir))
+  {
+    return Err(AgentError::InvalidPath(format!(
+      "'{}' must be a relative path inside {}",
+      name,
+      directory.display()
+    )));
+  }
+
+  let mut path = directory.join(relative);
+  if path.extension().is_none() {
+    path.set_extension("md");
+  }
+  Ok(path)
+}
+
+/// Suggest a file name from the first short string variable, or the template name
+pub fn default_file_name(template: &PromptTemplate, context: &TemplateContext) -> String {
+  let title = template
+    .variables
+    .iter()
+    .filter(|v| v.r#type == VariableType::String && !v.multiline)
+    .find_map(|v| {
+      context
+        .variables
+        .get(&v.name)
+        .and_then(|value| value.as_str())
+    })
+    .unwrap_or(&template.name);
+
+  let slug: String = title
+    .to_lowercase()
+    .chars()
+    .map(|c| if c.is_alphanumeric() { c } else { '-' })
+    .collect();
+  let slug = slug
+    .split('-')
+    .filter(|part| !part.is_empty())
+    .collect::<Vec<_>>()
+    .join("-");
+  format!("{}.md", if slug.is_empty() { "prompt" } else { &slug })
+}
+
+fn prompt_error(error: dialoguer::Error) -> AgentError {
+  AgentError::Internal(format!("Prompt failed: {}", error))
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  fn variable(r#type: VariableType) -> TemplateVariable {
+    TemplateVariable {
+      name: "v".to_string(),
+      description: String::new(),
+      r#type,
+      required: true,
+      default: None,
+      values: None,
+      multiline: false,
+    }
+  }
+
+  #[test]
+  fn test_prompt_kind_follows_variable_type() {
+    assert_eq!(
+      VariablePrompt::for_variable(&variable(VariableType::Boolean)),
+      VariablePrompt::Confirm
+    );
+    assert_eq!(
+      VariablePrompt::for_variable(&variable(VariableType::Number)),
+      VariablePrompt::Input
+    );
+    let long = TemplateVariable {
+      multiline: true,
+      ..variable(VariableType::String)
+    };
+    assert_eq!(VariablePrompt::for_variable(&long), VariablePrompt::Editor);
+    let choice = TemplateVariable {
+      values: Some(vec!["a".to_string()]),
+      ..long
+    };
+    assert_eq!(
+      VariablePrompt::for_variable(&choice),
+      VariablePrompt::Select(vec!["a".to_string()])
+    );
+  }
+
+  #[test]
+  fn test_resolve_target_stays_in_directory() {
+    let dir = Path::new("__ai/cmd");
+    assert_eq!(
+      resolve_target(dir, "review").unwrap(),
+      Path::new("__ai/cmd/review.md")
+    );
+    assert!(resolve_target(dir, "../escape.md").is_err());
+    assert!(resolve_target(dir, "/etc/passwd").is_err());
+  }
+}
@@ -0,0 +1,15 @@
+//! Snapshot tests for the template fixtures under `tests/fixtures/templates`
+
+use agents_core::TemplateRegistry;
+use agents_core::modules::snapshot;
+
+#[test]
+fn template_fixtures_match_snapshots() {
+  let dir = concat!(env!("CARGO_MANIFEST_DIR"), "/tests/fixtures/templates");
+  let mut registry = TemplateRegistry::new().unwrap();
+  registry.load_templates_from_directory(dir).unwrap();
+
+  let report = snapshot::run_snapshots(&registry, dir, false).unwrap();
+  assert_eq!(report.results.len(), 1);
+  report.assert_success();
+}